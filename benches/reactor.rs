@@ -0,0 +1,137 @@
+//! Measures what waiting on backends costs the client: the wall time of batches of concurrent DynamoDB calls, and how
+//! often the transport is called for them. Every poll, wait and select is a call into the Fastly host with
+//! [FastlyTransport](aws_fastly_http_client::FastlyTransport), so they're what to compare against the 5ms polling loop
+//! the reactor replaced, which polled every pending request 200 times a second. Run it in Viceroy with
+//! `cargo bench --target wasm32-wasi`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use aws_fastly_http_client::{
+    block_on, FastlyHttpClient, FastlySleep, FastlyTimeSource, InMemoryBody, InMemoryPending,
+    InMemoryTransport, Transport, TransportError, TransportPoll,
+};
+use aws_sdk_dynamodb::config::retry::RetryConfig;
+use aws_sdk_dynamodb::config::timeout::TimeoutConfig;
+use aws_sdk_dynamodb::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_smithy_runtime_api::box_error::BoxError;
+use fastly::backend::BackendBuilder;
+use fastly::{Backend, Request, Response};
+
+const ITEM: &str = r#"{"Item":{"path":{"S":"/"}}}"#;
+const BATCHES: u32 = 20;
+
+// Requests are waited on through whichever instance of the transport came first, so the counts are kept for all of them.
+static POLLS: AtomicUsize = AtomicUsize::new(0);
+static WAITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the calls made to an [InMemoryTransport].
+#[derive(Debug, Default)]
+struct CountingTransport {
+    inner: InMemoryTransport,
+}
+
+impl Transport for CountingTransport {
+    type Pending = InMemoryPending;
+    type StreamingBody = InMemoryBody;
+
+    fn send(&self, request: Request, backend: &Backend) -> Result<InMemoryPending, TransportError> {
+        self.inner.send(request, backend)
+    }
+
+    fn send_streaming(
+        &self,
+        request: Request,
+        backend: &Backend,
+    ) -> Result<(InMemoryBody, InMemoryPending), TransportError> {
+        self.inner.send_streaming(request, backend)
+    }
+
+    fn finish(&self, body: InMemoryBody) -> Result<(), BoxError> {
+        self.inner.finish(body)
+    }
+
+    fn register_backend(
+        &self,
+        name: &str,
+        builder: impl FnOnce(&str) -> BackendBuilder,
+    ) -> Result<Backend, BoxError> {
+        self.inner.register_backend(name, builder)
+    }
+
+    fn poll(&self, pending: InMemoryPending) -> TransportPoll<InMemoryPending> {
+        POLLS.fetch_add(1, Ordering::Relaxed);
+        self.inner.poll(pending)
+    }
+
+    fn wait(&self, pending: InMemoryPending) -> Result<Response, TransportError> {
+        WAITS.fetch_add(1, Ordering::Relaxed);
+        self.inner.wait(pending)
+    }
+
+    fn select(
+        &self,
+        pending: Vec<InMemoryPending>,
+    ) -> (
+        usize,
+        Result<Response, TransportError>,
+        Vec<InMemoryPending>,
+    ) {
+        WAITS.fetch_add(1, Ordering::Relaxed);
+        self.inner.select(pending)
+    }
+}
+
+fn main() {
+    println!("concurrency  latency/batch  polls/request  waits/request");
+
+    for concurrency in [1, 10, 100] {
+        let transport = CountingTransport::default();
+        POLLS.store(0, Ordering::Relaxed);
+        WAITS.store(0, Ordering::Relaxed);
+
+        for _ in 0..BATCHES * concurrency {
+            transport.inner.respond_after(2, Response::from_body(ITEM));
+        }
+
+        let config = aws_sdk_dynamodb::Config::builder()
+            .region(Region::from_static("us-east-1"))
+            .credentials_provider(Credentials::new("AKID", "SECRET", None, None, "bench"))
+            .http_client(FastlyHttpClient::dynamic().with_transport(transport))
+            .sleep_impl(FastlySleep)
+            .time_source(FastlyTimeSource)
+            .retry_config(RetryConfig::disabled())
+            .timeout_config(TimeoutConfig::disabled())
+            .behavior_version(BehaviorVersion::v2023_11_09())
+            .build();
+        let client = aws_sdk_dynamodb::Client::from_conf(config);
+
+        let mut elapsed = Duration::ZERO;
+
+        for _ in 0..BATCHES {
+            let calls = (0..concurrency).map(|_| {
+                client
+                    .get_item()
+                    .table_name("paths")
+                    .key("path", AttributeValue::S("/".to_string()))
+                    .send()
+            });
+
+            let started = Instant::now();
+            for output in block_on(futures::future::join_all(calls)) {
+                output.expect("scripted call failed");
+            }
+            elapsed += started.elapsed();
+        }
+
+        let requests = (BATCHES * concurrency) as f64;
+
+        println!(
+            "{concurrency:>11}  {:>13?}  {:>13.1}  {:>13.1}",
+            elapsed / BATCHES,
+            POLLS.load(Ordering::Relaxed) as f64 / requests,
+            WAITS.load(Ordering::Relaxed) as f64 / requests,
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aws_credential_types::provider::error::CredentialsError;
+use aws_credential_types::provider::{future, ProvideCredentials, SharedCredentialsProvider};
+use aws_credential_types::Credentials;
+use aws_types::SdkConfig;
+use fastly::cache::core::{self, CacheKey};
+use serde::{Deserialize, Serialize};
+
+const PROVIDER_NAME: &str = "FastlyAssumeRole";
+
+/// Provides temporary credentials for an IAM role, assumed with STS `AssumeRole`, so no long-lived keys with access to
+/// your resources have to be deployed to the edge:
+///
+/// ```no_run
+/// use aws_credential_types::provider::SharedCredentialsProvider;
+/// use aws_fastly_http_client::{
+///     FastlyAssumeRoleCredentialsProvider, FastlyHttpClient, FastlySecretStoreCredentialsProvider,
+/// };
+/// use aws_types::region::Region;
+///
+/// // Only allowed to assume the role.
+/// let sts_config = aws_fastly_http_client::sdk_config(FastlyHttpClient::dynamic())
+///     .region(Region::from_static("us-east-1"))
+///     .credentials_provider(SharedCredentialsProvider::new(
+///         FastlySecretStoreCredentialsProvider::new("aws_credentials"),
+///     ))
+///     .build();
+///
+/// let credentials_provider = FastlyAssumeRoleCredentialsProvider::new(
+///     &sts_config,
+///     "arn:aws:iam::123456789012:role/edge",
+/// );
+///
+/// let sdk_config = aws_fastly_http_client::sdk_config("dynamodb")
+///     .region(Region::from_static("us-east-1"))
+///     .credentials_provider(SharedCredentialsProvider::new(credentials_provider))
+///     .build();
+/// ```
+///
+/// STS is called with the HTTP client and credentials of the given [SdkConfig], so its client has to be able to reach
+/// the STS endpoint. Compute instances don't live long enough to reuse credentials in memory, so they're kept in the
+/// Core Cache of the POP, which is private to your service, until shortly before they expire. They're keyed by role ARN,
+/// session name, duration and the access key of the credentials STS is called with, so only the first request in a
+/// while pays for the round trip to STS. Failing to read or write the cache isn't an error, the credentials are just
+/// fetched again.
+#[derive(Clone, Debug)]
+pub struct FastlyAssumeRoleCredentialsProvider {
+    client: aws_sdk_sts::Client,
+    source: Option<SharedCredentialsProvider>,
+    role_arn: String,
+    session_name: String,
+    duration: Option<Duration>,
+    expiry_skew: Duration,
+}
+
+impl FastlyAssumeRoleCredentialsProvider {
+    /// Assumes the role with the ARN `role_arn`, calling STS with the HTTP client and credentials in `sdk_config`.
+    pub fn new(sdk_config: &SdkConfig, role_arn: impl Into<String>) -> Self {
+        Self {
+            client: aws_sdk_sts::Client::new(sdk_config),
+            source: sdk_config.credentials_provider(),
+            role_arn: role_arn.into(),
+            session_name: "aws-fastly-http-client".to_string(),
+            duration: None,
+            expiry_skew: Duration::from_secs(5 * 60),
+        }
+    }
+
+    /// Names the role session, which shows up in CloudTrail. Defaults to `aws-fastly-http-client`.
+    pub fn with_session_name(mut self, session_name: impl Into<String>) -> Self {
+        self.session_name = session_name.into();
+        self
+    }
+
+    /// Asks STS for credentials that last this long, instead of the role's default of an hour.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Stops using cached credentials this long before they expire. Defaults to 5 minutes.
+    pub fn with_expiry_skew(mut self, expiry_skew: Duration) -> Self {
+        self.expiry_skew = expiry_skew;
+        self
+    }
+
+    async fn load(&self) -> Result<Credentials, CredentialsError> {
+        let duration_seconds = self
+            .duration
+            .map(|duration| i32::try_from(duration.as_secs()))
+            .transpose()
+            .map_err(|_| CredentialsError::invalid_configuration("duration is too long for STS"))?;
+
+        let source = match &self.source {
+            Some(source) => Some(source.provide_credentials().await?),
+            None => None,
+        };
+        let cache_key = self.cache_key(duration_seconds, source.as_ref());
+
+        if let Some(credentials) = self.cached(&cache_key) {
+            return Ok(credentials.into());
+        }
+
+        let output = self
+            .client
+            .assume_role()
+            .role_arn(&self.role_arn)
+            .role_session_name(&self.session_name)
+            .set_duration_seconds(duration_seconds)
+            .send()
+            .await
+            .map_err(CredentialsError::provider_error)?;
+
+        let credentials = output
+            .credentials
+            .ok_or_else(|| CredentialsError::unhandled("STS returned no credentials"))?;
+
+        let credentials = CachedCredentials {
+            access_key_id: credentials.access_key_id,
+            secret_access_key: credentials.secret_access_key,
+            session_token: credentials.session_token,
+            expires_at: credentials.expiration.secs(),
+        };
+
+        self.cache(cache_key, &credentials);
+
+        Ok(credentials.into())
+    }
+
+    /// Credentials are only shared between providers that would get the same ones from STS.
+    fn cache_key(&self, duration_seconds: Option<i32>, source: Option<&Credentials>) -> CacheKey {
+        CacheKey::from(format!(
+            "aws-fastly-http-client/assume-role/{}/{}/{}/{}",
+            self.role_arn,
+            self.session_name,
+            duration_seconds.map_or_else(|| "default".to_string(), |secs| secs.to_string()),
+            source.map_or("none", |source| source.access_key_id()),
+        ))
+    }
+
+    fn cached(&self, cache_key: &CacheKey) -> Option<CachedCredentials> {
+        let found = core::lookup(cache_key.clone()).execute().ok()??;
+        let credentials: CachedCredentials =
+            serde_json::from_reader(found.to_stream().ok()?).ok()?;
+
+        credentials.ttl(self.expiry_skew).map(|_| credentials)
+    }
+
+    fn cache(&self, cache_key: CacheKey, credentials: &CachedCredentials) {
+        let Some(ttl) = credentials.ttl(self.expiry_skew) else {
+            return;
+        };
+
+        // Dropping the body without finishing it abandons the insert.
+        let Ok(mut body) = core::insert(cache_key, ttl).execute() else {
+            return;
+        };
+
+        if serde_json::to_writer(&mut body, credentials).is_ok() {
+            let _ = body.finish();
+        }
+    }
+}
+
+impl ProvideCredentials for FastlyAssumeRoleCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.load())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+    /// Seconds since the Unix epoch.
+    expires_at: i64,
+}
+
+impl CachedCredentials {
+    /// How much longer the credentials can be used, if they aren't about to expire.
+    fn ttl(&self, expiry_skew: Duration) -> Option<Duration> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        let expires_at = Duration::from_secs(u64::try_from(self.expires_at).ok()?);
+
+        expires_at
+            .checked_sub(expiry_skew)?
+            .checked_sub(now)
+            .filter(|ttl| !ttl.is_zero())
+    }
+}
+
+impl From<CachedCredentials> for Credentials {
+    fn from(credentials: CachedCredentials) -> Self {
+        Credentials::new(
+            credentials.access_key_id,
+            credentials.secret_access_key,
+            Some(credentials.session_token),
+            Some(UNIX_EPOCH + Duration::from_secs(credentials.expires_at.max(0) as u64)),
+            PROVIDER_NAME,
+        )
+    }
+}
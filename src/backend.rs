@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use aws_smithy_runtime_api::client::http::HttpConnectorSettings;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use fastly::backend::BackendBuilder;
+use fastly::{Backend, Request};
+
+use crate::error::{BackendError, NoRouteError};
+use crate::failover::Failover;
+use crate::routes::Routes;
+use crate::transport::Transport;
+
+/// Where a client sends its requests.
+#[derive(Clone, Debug)]
+pub(crate) enum Target {
+    /// Every request goes to the same backend.
+    Backend(Backend),
+    /// Requests go to the backend routed for their host.
+    Routes(Routes),
+    /// Every request goes to a dynamic backend for the host and port of the endpoint the SDK resolved.
+    Endpoint,
+    /// Requests go to the first backend that works.
+    Failover(Failover),
+}
+
+/// The timeouts from [HttpConnectorSettings] that Fastly can enforce per backend.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub(crate) struct Timeouts {
+    connect: Option<Duration>,
+    read: Option<Duration>,
+}
+
+impl From<&HttpConnectorSettings> for Timeouts {
+    fn from(settings: &HttpConnectorSettings) -> Self {
+        Self {
+            connect: settings.connect_timeout(),
+            read: settings.read_timeout(),
+        }
+    }
+}
+
+impl Timeouts {
+    fn is_empty(&self) -> bool {
+        self.connect.is_none() && self.read.is_none()
+    }
+
+    fn apply(&self, mut builder: BackendBuilder) -> BackendBuilder {
+        if let Some(timeout) = self.connect {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.read {
+            builder = builder
+                .first_byte_timeout(timeout)
+                .between_bytes_timeout(timeout);
+        }
+
+        builder
+    }
+
+    fn suffix(&self) -> String {
+        format!("{}_{}", millis(self.connect), millis(self.read))
+    }
+}
+
+/// The dynamic backends a client has registered, by name. Fastly only lets you set timeouts on a backend, not on a
+/// request, so to honor the SDK's timeouts a dynamic backend is registered for every combination of target and
+/// timeouts that's used, through the client's [Transport], and reused for the rest of the instance.
+#[derive(Debug, Default)]
+pub(crate) struct Backends {
+    registered: Mutex<HashMap<String, Backend>>,
+}
+
+impl Backends {
+    /// Returns the backend to send the request to.
+    pub(crate) fn resolve<T: Transport>(
+        &self,
+        transport: &T,
+        target: &Target,
+        request: &Request,
+        timeouts: Timeouts,
+    ) -> Result<Backend, ConnectorError> {
+        let url = request.get_url();
+
+        let backend = match target {
+            Target::Backend(backend) => backend,
+            // Only requests with streaming bodies are resolved here, and they can't be signed again for other
+            // backends, so they go to the one they were signed for. The connector fails over buffered requests.
+            Target::Failover(failover) => {
+                let Some(backend) = failover.first() else {
+                    return Err(BackendError::new("failover", "no backends to fail over to").into());
+                };
+
+                backend
+            }
+            Target::Routes(routes) => {
+                let host = url.host_str().unwrap_or_default();
+                routes
+                    .backend_for(host)
+                    .ok_or_else(|| NoRouteError::new(host))?
+            }
+            Target::Endpoint => {
+                let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+                    return Err(BackendError::new(url.as_str(), "request URL has no host").into());
+                };
+
+                let tls = url.scheme() == "https";
+                let name = format!("{}_{}_{}", sanitize(host), port, timeouts.suffix());
+
+                return Ok(self.get_or_register(transport, name, |name| {
+                    endpoint(name, host, port, tls, timeouts)
+                })?);
+            }
+        };
+
+        if timeouts.is_empty() {
+            return Ok(backend.clone());
+        }
+
+        let name = format!("{}_{}", backend.name(), timeouts.suffix());
+
+        match self.get_or_register(transport, name.clone(), |name| {
+            derive(name, backend, timeouts)
+        }) {
+            Ok(derived) => Ok(derived),
+            // Most likely dynamic backends aren't enabled for the service. The backend still works as declared, with
+            // its own timeouts, so it's used from now on rather than failing every request.
+            Err(_) => {
+                let mut registered = self.registered.lock().unwrap();
+                Ok(registered
+                    .entry(name)
+                    .or_insert_with(|| backend.clone())
+                    .clone())
+            }
+        }
+    }
+
+    fn get_or_register<T: Transport>(
+        &self,
+        transport: &T,
+        name: String,
+        builder: impl FnOnce(&str) -> BackendBuilder,
+    ) -> Result<Backend, BackendError> {
+        let mut registered = self.registered.lock().unwrap();
+
+        if let Some(backend) = registered.get(&name) {
+            return Ok(backend.clone());
+        }
+
+        let backend = transport
+            .register_backend(&name, builder)
+            .map_err(|error| BackendError::new(&name, error))?;
+
+        registered.insert(name, backend.clone());
+
+        Ok(backend)
+    }
+}
+
+/// A builder for a backend that sends requests like `backend` does, with the same TLS and keepalive settings, but
+/// applies the given timeouts. Timeouts that aren't given are copied from `backend` too.
+fn derive(name: &str, backend: &Backend, timeouts: Timeouts) -> BackendBuilder {
+    let host = backend.get_host();
+    let host_override = backend.get_host_override();
+
+    let mut builder = Backend::builder(name, format!("{}:{}", host, backend.get_port()))
+        .connect_timeout(backend.get_connect_timeout())
+        .first_byte_timeout(backend.get_first_byte_timeout())
+        .between_bytes_timeout(backend.get_between_bytes_timeout());
+
+    if let Some(host_override) = &host_override {
+        builder = builder.override_host(host_override);
+    }
+
+    if backend.is_ssl() {
+        let hostname = host_override.as_ref().unwrap_or(&host);
+        builder = builder
+            .enable_ssl()
+            .sni_hostname(hostname)
+            .check_certificate(hostname);
+
+        if let Ok(Some(version)) = backend.get_ssl_min_version() {
+            builder = builder.set_min_tls_version(version);
+        }
+
+        if let Ok(Some(version)) = backend.get_ssl_max_version() {
+            builder = builder.set_max_tls_version(version);
+        }
+    }
+
+    if let Ok(time) = backend.get_http_keepalive_time() {
+        builder = builder.http_keepalive_time(time);
+    }
+
+    if let Ok(enabled) = backend.get_tcp_keepalive_enable() {
+        builder = builder.tcp_keepalive_enable(enabled);
+    }
+
+    if let Some(secs) = secs(backend.get_tcp_keepalive_interval().ok()) {
+        builder = builder.tcp_keepalive_interval_secs(secs);
+    }
+
+    if let Ok(probes) = backend.get_tcp_keepalive_probes() {
+        builder = builder.tcp_keepalive_probes(probes);
+    }
+
+    if let Some(secs) = secs(backend.get_tcp_keepalive_time().ok()) {
+        builder = builder.tcp_keepalive_time_secs(secs);
+    }
+
+    timeouts.apply(builder)
+}
+
+/// A builder for a backend that sends requests to an AWS endpoint.
+fn endpoint(name: &str, host: &str, port: u16, tls: bool, timeouts: Timeouts) -> BackendBuilder {
+    let mut builder = Backend::builder(name, format!("{}:{}", host, port)).override_host(host);
+
+    if tls {
+        builder = builder
+            .enable_ssl()
+            .sni_hostname(host)
+            .check_certificate(host);
+    }
+
+    timeouts.apply(builder)
+}
+
+fn sanitize(host: &str) -> String {
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn secs(duration: Option<Duration>) -> Option<u32> {
+    duration.and_then(|duration| u32::try_from(duration.as_secs()).ok())
+}
+
+fn millis(timeout: Option<Duration>) -> String {
+    timeout
+        .map(|timeout| timeout.as_millis().to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
@@ -0,0 +1,110 @@
+use std::future::poll_fn;
+use std::io::{ErrorKind, Read, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use fastly::Body;
+use http::HeaderMap;
+use http_body::Body as _;
+
+use crate::transport::Transport;
+
+/// How much of a response body is read from Fastly per chunk handed to the SDK.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Copies a streaming [SdkBody] into the streaming body of a request chunk by chunk, finishing the body once the stream
+/// is exhausted. If this fails, the streaming body is dropped without being finished, which aborts the request.
+pub(crate) async fn pump<T: Transport>(
+    body: SdkBody,
+    mut streaming_body: T::StreamingBody,
+    transport: &T,
+) -> Result<(), ConnectorError> {
+    let mut body = Box::pin(body);
+
+    while let Some(chunk) = poll_fn(|cx| Pin::as_mut(&mut body).poll_data(cx)).await {
+        let chunk = chunk.map_err(|error| ConnectorError::other(error, None))?;
+
+        streaming_body
+            .write_all(&chunk)
+            .map_err(|error| ConnectorError::io(Box::new(error)))?;
+    }
+
+    transport.finish(streaming_body).map_err(ConnectorError::io)
+}
+
+/// Reads all of an [SdkBody] into memory.
+pub(crate) async fn collect(body: SdkBody) -> Result<Vec<u8>, BoxError> {
+    let mut body = Box::pin(body);
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = poll_fn(|cx| Pin::as_mut(&mut body).poll_data(cx)).await {
+        bytes.extend_from_slice(&chunk?);
+    }
+
+    Ok(bytes)
+}
+
+/// Exposes a Fastly [Body] as an [http_body::Body], so response bodies are read from the backend as the SDK consumes
+/// them rather than buffered up front.
+pub(crate) struct ResponseBody {
+    body: Option<Body>,
+}
+
+impl From<Body> for ResponseBody {
+    fn from(body: Body) -> Self {
+        Self { body: Some(body) }
+    }
+}
+
+impl http_body::Body for ResponseBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let Some(body) = self.body.as_mut() else {
+            return Poll::Ready(None);
+        };
+
+        let mut chunk = vec![0; CHUNK_SIZE];
+
+        let result = loop {
+            match body.read(&mut chunk) {
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+
+        match result {
+            Ok(0) => {
+                self.body = None;
+                Poll::Ready(None)
+            }
+            Ok(read) => {
+                chunk.truncate(read);
+                Poll::Ready(Some(Ok(Bytes::from(chunk))))
+            }
+            Err(error) => {
+                self.body = None;
+                Poll::Ready(Some(Err(error)))
+            }
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(None))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_none()
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use fastly::http::header::RANGE;
+use fastly::http::{HeaderValue, Method};
+use fastly::Request;
+
+/// Query parameters of presigned URLs, which differ for every signature of the same request.
+const SIGNATURE_PARAMS: &[&str] = &[
+    "X-Amz-Algorithm",
+    "X-Amz-Credential",
+    "X-Amz-Date",
+    "X-Amz-Expires",
+    "X-Amz-Security-Token",
+    "X-Amz-Signature",
+    "X-Amz-SignedHeaders",
+];
+
+type Policy = dyn Fn(&Request) -> bool + Send + Sync;
+type SurrogateKeys = dyn Fn(&Request) -> Vec<String> + Send + Sync;
+
+/// Caches responses to AWS requests at the POP, so repeated reads of the same object don't go to AWS every time.
+/// Enable it with [FastlyHttpClient::with_caching](crate::FastlyHttpClient::with_caching):
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aws_fastly_http_client::{CachePolicy, FastlyHttpClient};
+///
+/// let caching = CachePolicy::new(Duration::from_secs(60)).stale_while_revalidate(Duration::from_secs(300));
+/// let http_client = FastlyHttpClient::from("s3").with_caching(caching);
+/// ```
+///
+/// Only requests the policy allows are cached. By default, that's `GET` and `HEAD` requests, such as S3 `GetObject`
+/// and `HeadObject`. Use [CachePolicy::when] for anything else. Requests with streaming bodies are never cached.
+///
+/// The cache key is the host, path and query of the request, and its `Range` header. Signatures are left out of it, so
+/// requests signed at different times, or with different credentials, share the same cached response. Only cache
+/// responses every caller of the client may read.
+///
+/// S3 object responses are tagged with the surrogate keys `s3/<bucket>` and `s3/<bucket>/<key>`, so they can be
+/// purged when an object changes. Use [CachePolicy::surrogate_keys] to tag responses differently.
+#[derive(Clone)]
+pub struct CachePolicy {
+    ttl: Duration,
+    stale_while_revalidate: Option<Duration>,
+    policy: Arc<Policy>,
+    surrogate_keys: Arc<SurrogateKeys>,
+}
+
+impl CachePolicy {
+    /// Caches responses for `ttl`, whatever their `Cache-Control` headers say.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            stale_while_revalidate: None,
+            policy: Arc::new(is_read),
+            surrogate_keys: Arc::new(s3_surrogate_keys),
+        }
+    }
+
+    /// Keeps serving responses for up to `stale_while_revalidate` after they expire, while they're fetched again.
+    pub fn stale_while_revalidate(mut self, stale_while_revalidate: Duration) -> Self {
+        self.stale_while_revalidate = Some(stale_while_revalidate);
+        self
+    }
+
+    /// Only caches responses to requests `policy` returns true for, instead of the default policy.
+    pub fn when(mut self, policy: impl Fn(&Request) -> bool + Send + Sync + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Tags responses with the surrogate keys `surrogate_keys` returns for their request, instead of the S3 ones.
+    pub fn surrogate_keys(
+        mut self,
+        surrogate_keys: impl Fn(&Request) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.surrogate_keys = Arc::new(surrogate_keys);
+        self
+    }
+
+    /// Sets the cache key and the cache override of `request`, if its response can be cached.
+    pub(crate) fn apply(&self, request: &mut Request) {
+        if !(self.policy)(request) {
+            return;
+        }
+
+        request.set_cache_key(cache_key(request));
+        request.set_ttl(seconds(self.ttl));
+
+        if let Some(stale_while_revalidate) = self.stale_while_revalidate {
+            request.set_stale_while_revalidate(seconds(stale_while_revalidate));
+        }
+
+        let surrogate_keys = (self.surrogate_keys)(request).join(" ");
+
+        if let Ok(surrogate_keys) = HeaderValue::from_str(&surrogate_keys) {
+            if !surrogate_keys.is_empty() {
+                request.set_surrogate_key(surrogate_keys);
+            }
+        }
+    }
+}
+
+impl Debug for CachePolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachePolicy")
+            .field("ttl", &self.ttl)
+            .field("stale_while_revalidate", &self.stale_while_revalidate)
+            .finish_non_exhaustive()
+    }
+}
+
+fn seconds(duration: Duration) -> u32 {
+    u32::try_from(duration.as_secs()).unwrap_or(u32::MAX)
+}
+
+/// The default policy: requests that only read.
+fn is_read(request: &Request) -> bool {
+    matches!(request.get_method(), &Method::GET | &Method::HEAD)
+}
+
+/// Identifies the response to `request`, leaving out anything that changes when it's signed again.
+fn cache_key(request: &Request) -> String {
+    let url = request.get_url();
+
+    let query = url
+        .query_pairs()
+        .filter(|(name, _)| !SIGNATURE_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let range = request.get_header_str(RANGE).unwrap_or_default();
+
+    format!(
+        "{}{}?{query}\n{range}",
+        url.host_str().unwrap_or_default(),
+        url.path()
+    )
+}
+
+/// The default surrogate keys: the bucket and the object of S3 requests, for virtual-hosted and path-style URLs.
+fn s3_surrogate_keys(request: &Request) -> Vec<String> {
+    let url = request.get_url();
+    let host = url.host_str().unwrap_or_default();
+    let path = url.path().trim_start_matches('/');
+
+    let (bucket, key) = match host.split_once(".s3.").or_else(|| host.split_once(".s3-")) {
+        Some((bucket, _)) => (bucket, path),
+        None if host.starts_with("s3.") || host.starts_with("s3-") => {
+            path.split_once('/').unwrap_or((path, ""))
+        }
+        None => return Vec::new(),
+    };
+
+    if bucket.is_empty() {
+        return Vec::new();
+    }
+
+    let mut surrogate_keys = vec![format!("s3/{bucket}")];
+    if !key.is_empty() {
+        surrogate_keys.push(format!("s3/{bucket}/{key}"));
+    }
+
+    surrogate_keys
+}
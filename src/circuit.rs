@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use fastly::cache::core::{self, CacheKey};
+use fastly::Response;
+
+use crate::error::CircuitOpenError;
+use crate::transport::TransportError;
+
+/// How long a closed circuit goes without checking whether another instance opened it.
+const SHARED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Stops sending requests to a backend that keeps failing, so callers fail fast with a [CircuitOpenError] instead of
+/// waiting for every request to fail. Enable it with
+/// [FastlyHttpClient::with_circuit_breaker](crate::FastlyHttpClient::with_circuit_breaker):
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aws_fastly_http_client::{CircuitBreaker, FastlyHttpClient};
+///
+/// let http_client = FastlyHttpClient::from("dynamodb").with_circuit_breaker(
+///     CircuitBreaker::new()
+///         .failure_rate(0.5)
+///         .open_for(Duration::from_secs(30))
+///         .shared(),
+/// );
+/// ```
+///
+/// Requests that can't be sent and 5xx responses count as failures. Once at least
+/// [minimum_requests](CircuitBreaker::minimum_requests) were sent to a backend within a
+/// [window](CircuitBreaker::window) and the share of failures reaches the [failure_rate](CircuitBreaker::failure_rate),
+/// the circuit opens. After [open_for](CircuitBreaker::open_for), a single request is let through to probe the
+/// backend, closing the circuit if it succeeds and opening it again if it doesn't.
+///
+/// Failures are counted per Compute instance, which usually only lives for one client request. With
+/// [shared](CircuitBreaker::shared), opening a circuit is also recorded in the Core Cache of the POP, so other instances
+/// fail fast too until it's time to probe again. Closed circuits look it up at most once a second, so requests don't all
+/// pay for a cache lookup.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_rate: f64,
+    minimum_requests: u32,
+    window: Duration,
+    open_for: Duration,
+    shared: bool,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            minimum_requests: 10,
+            window: Duration::from_secs(10),
+            open_for: Duration::from_secs(30),
+            shared: false,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a circuit breaker that opens when half of at least 10 requests within 10 seconds fail, for 30 seconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the circuit when this share of requests fails, between 0 and 1.
+    pub fn failure_rate(mut self, failure_rate: f64) -> Self {
+        self.failure_rate = failure_rate;
+        self
+    }
+
+    /// Keeps the circuit closed until at least this many requests were sent within the window.
+    pub fn minimum_requests(mut self, minimum_requests: u32) -> Self {
+        self.minimum_requests = minimum_requests;
+        self
+    }
+
+    /// Starts counting requests and failures anew this often.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Fails requests this long after opening the circuit, before probing the backend again.
+    pub fn open_for(mut self, open_for: Duration) -> Self {
+        self.open_for = open_for;
+        self
+    }
+
+    /// Shares open circuits with other instances through the Core Cache.
+    pub fn shared(mut self) -> Self {
+        self.shared = true;
+        self
+    }
+}
+
+/// The circuits of a client's backends, by backend name. Without a [CircuitBreaker], every request goes through.
+#[derive(Debug, Default)]
+pub(crate) struct Circuits {
+    breaker: Option<CircuitBreaker>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+#[derive(Debug)]
+enum Circuit {
+    /// Counting since `since`. With [CircuitBreaker::shared], `checked` is when the Core Cache was last checked.
+    Closed {
+        since: Instant,
+        requests: u32,
+        failures: u32,
+        checked: Option<Instant>,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe was let through at `since`.
+    HalfOpen {
+        since: Instant,
+    },
+}
+
+impl Circuit {
+    fn closed() -> Self {
+        Circuit::Closed {
+            since: Instant::now(),
+            requests: 0,
+            failures: 0,
+            checked: None,
+        }
+    }
+}
+
+impl Circuits {
+    pub(crate) fn new(breaker: CircuitBreaker) -> Self {
+        Self {
+            breaker: Some(breaker),
+            circuits: Mutex::default(),
+        }
+    }
+
+    /// Fails if requests to `backend` shouldn't be sent right now.
+    pub(crate) fn check(&self, backend: &str) -> Result<(), CircuitOpenError> {
+        let Some(breaker) = &self.breaker else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits
+            .entry(backend.to_string())
+            .or_insert_with(Circuit::closed);
+
+        match circuit {
+            Circuit::Closed { checked, .. }
+                if breaker.shared
+                    && !checked.is_some_and(|checked| now < checked + SHARED_CHECK_INTERVAL) =>
+            {
+                *checked = Some(now);
+
+                if !is_open_elsewhere(backend) {
+                    return Ok(());
+                }
+
+                *circuit = Circuit::Open {
+                    until: now + breaker.open_for,
+                };
+                Err(CircuitOpenError::new(backend))
+            }
+            Circuit::Closed { .. } => Ok(()),
+            Circuit::Open { until } if now < *until => Err(CircuitOpenError::new(backend)),
+            Circuit::HalfOpen { since } if now < *since + breaker.open_for => {
+                Err(CircuitOpenError::new(backend))
+            }
+            // Probe once the circuit has been open long enough, or again if the last probe was abandoned.
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => {
+                *circuit = Circuit::HalfOpen { since: now };
+                Ok(())
+            }
+        }
+    }
+
+    /// Counts the outcome of a request to `backend`.
+    pub(crate) fn record(&self, backend: &str, result: &Result<Response, TransportError>) {
+        let failed = match result {
+            Ok(response) => response.get_status().is_server_error(),
+            Err(_) => true,
+        };
+
+        self.count(backend, failed);
+    }
+
+    /// Counts a request to `backend` that couldn't be sent.
+    pub(crate) fn record_failure(&self, backend: &str) {
+        self.count(backend, true);
+    }
+
+    fn count(&self, backend: &str, failed: bool) {
+        let Some(breaker) = &self.breaker else {
+            return;
+        };
+
+        let now = Instant::now();
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits
+            .entry(backend.to_string())
+            .or_insert_with(Circuit::closed);
+
+        let open = match circuit {
+            Circuit::Closed {
+                since,
+                requests,
+                failures,
+                ..
+            } => {
+                if now >= *since + breaker.window {
+                    *since = now;
+                    *requests = 0;
+                    *failures = 0;
+                }
+
+                *requests += 1;
+                *failures += u32::from(failed);
+
+                *requests >= breaker.minimum_requests
+                    && f64::from(*failures) >= breaker.failure_rate * f64::from(*requests)
+            }
+            Circuit::HalfOpen { .. } if failed => true,
+            Circuit::HalfOpen { .. } => {
+                *circuit = Circuit::closed();
+                false
+            }
+            // Sent before the circuit opened.
+            Circuit::Open { .. } => false,
+        };
+
+        if open {
+            *circuit = Circuit::Open {
+                until: now + breaker.open_for,
+            };
+
+            if breaker.shared {
+                share_open(backend, breaker.open_for);
+            }
+        }
+    }
+}
+
+fn cache_key(backend: &str) -> CacheKey {
+    CacheKey::from(format!("aws-fastly-http-client/circuit/{backend}"))
+}
+
+/// Whether another instance opened the circuit of `backend`. Cache failures count as closed.
+fn is_open_elsewhere(backend: &str) -> bool {
+    matches!(core::lookup(cache_key(backend)).execute(), Ok(Some(_)))
+}
+
+/// Tells other instances the circuit of `backend` is open, for as long as it stays open.
+fn share_open(backend: &str, open_for: Duration) {
+    if let Ok(body) = core::insert(cache_key(backend), open_for).execute() {
+        let _ = body.finish();
+    }
+}
@@ -0,0 +1,31 @@
+use fastly::http::Method;
+use fastly::Request;
+
+/// Headers that change every time a request is signed or sent, so requests that only differ in them are the same
+/// request.
+pub(crate) const VOLATILE_HEADERS: &[&str] = &[
+    "authorization",
+    "x-amz-date",
+    "x-amz-security-token",
+    "amz-sdk-invocation-id",
+    "amz-sdk-request",
+    "user-agent",
+    "x-amz-user-agent",
+];
+
+/// DynamoDB operations that only read, and are safe to send twice.
+const DYNAMODB_READS: &[&str] = &["GetItem", "BatchGetItem", "Query", "Scan"];
+
+/// Whether `request` only reads, so it's safe to send twice or to answer with the response to an identical one:
+/// `GET` and `HEAD` requests, and DynamoDB `GetItem`, `BatchGetItem`, `Query` and `Scan` calls.
+pub(crate) fn is_read(request: &Request) -> bool {
+    if matches!(request.get_method(), &Method::GET | &Method::HEAD) {
+        return true;
+    }
+
+    request
+        .get_header_str("x-amz-target")
+        .and_then(|target| target.strip_prefix("DynamoDB_"))
+        .and_then(|target| target.split_once('.'))
+        .is_some_and(|(_, operation)| DYNAMODB_READS.contains(&operation))
+}
@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::io::{Read, Write};
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use aws_smithy_runtime_api::client::http::HttpConnectorFuture;
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use fastly::cache::core::{CacheKey, Found, Transaction};
+use fastly::Request;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::body;
+use crate::classify::{self, VOLATILE_HEADERS};
+use crate::error::ConversionError;
+
+type Policy = dyn Fn(&Request) -> bool + Send + Sync;
+
+/// The largest response body that's buffered for identical requests by default.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// Sends identical requests that are in flight at the same time only once, and hands the response to all of them, so a
+/// burst of reads of the same S3 object or DynamoDB item makes one trip to AWS. Enable it with
+/// [FastlyHttpClient::with_collapsing](crate::FastlyHttpClient::with_collapsing):
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aws_fastly_http_client::{Collapsing, FastlyHttpClient};
+///
+/// let http_client = FastlyHttpClient::from("dynamodb").with_collapsing(Collapsing::new().shared(Duration::from_secs(1)));
+/// ```
+///
+/// Requests are identical when they have the same method, URI, headers and body, leaving out headers that change every
+/// time a request is signed. Only requests the policy allows are collapsed. By default, that's `GET` and `HEAD` requests,
+/// and DynamoDB `GetItem`, `BatchGetItem`, `Query` and `Scan` calls. Use [Collapsing::when] for anything else, but only
+/// for requests that are safe to answer with the response to another one. Requests with streaming bodies are never
+/// collapsed. If the request that was sent fails, the others are sent on their own.
+///
+/// The response to a request that's sent on behalf of others is buffered in memory to hand it to all of them, which
+/// costs up to [max_body_size](Collapsing::max_body_size), 1 MiB by default, for every response that's collapsed. It's
+/// only buffered when other requests are waiting on it or it's shared. Larger responses, and ones without a
+/// `Content-Length`, are streamed to the request that was sent, and the others are sent on their own.
+///
+/// Requests are collapsed per Compute instance. With [shared](Collapsing::shared), they're also collapsed with the
+/// other instances of the POP through the Core Cache, which keeps successful responses for a while so instances that
+/// ask a bit later get them too. An instance waiting on another one is blocked until that one gets its response.
+///
+/// Collapsed requests share their response whatever credentials signed them, so only collapse requests every caller of
+/// the client may make.
+#[derive(Clone)]
+pub struct Collapsing {
+    policy: Arc<Policy>,
+    shared: Option<Duration>,
+    max_body_size: u64,
+}
+
+impl Default for Collapsing {
+    fn default() -> Self {
+        Self {
+            policy: Arc::new(classify::is_read),
+            shared: None,
+            max_body_size: MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl Collapsing {
+    /// Collapses identical reads within a Compute instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only collapses requests `policy` returns true for, instead of the default policy.
+    pub fn when(mut self, policy: impl Fn(&Request) -> bool + Send + Sync + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Also collapses requests with other instances through the Core Cache, keeping successful responses for `ttl`.
+    pub fn shared(mut self, ttl: Duration) -> Self {
+        self.shared = Some(ttl);
+        self
+    }
+
+    /// Only hands responses with bodies up to this many bytes to identical requests, instead of 1 MiB.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl Debug for Collapsing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Collapsing")
+            .field("shared", &self.shared)
+            .field("max_body_size", &self.max_body_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The requests a client has in flight, by what makes them identical. Without [Collapsing], nothing is collapsed.
+#[derive(Debug, Default)]
+pub(crate) struct Collapser {
+    collapsing: Option<Collapsing>,
+    in_flight: Mutex<HashMap<String, Arc<Slot>>>,
+}
+
+impl Collapser {
+    pub(crate) fn new(collapsing: Collapsing) -> Self {
+        Self {
+            collapsing: Some(collapsing),
+            in_flight: Mutex::default(),
+        }
+    }
+
+    /// Whether `request` can be collapsed with identical ones.
+    pub(crate) fn applies_to(&self, request: &Request) -> bool {
+        self.collapsing
+            .as_ref()
+            .is_some_and(|collapsing| (collapsing.policy)(request))
+    }
+
+    /// Waits for the response to an identical request if one is in flight, and otherwise uses `send` to send `request`
+    /// on behalf of every identical request made until it completes.
+    pub(crate) fn collapse(
+        self: &Arc<Self>,
+        mut request: Request,
+        send: impl FnOnce(Request) -> HttpConnectorFuture + Send + 'static,
+    ) -> HttpConnectorFuture {
+        let key = collapse_key(&mut request);
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        if let Some(slot) = in_flight.get(&key) {
+            slot.waiters.fetch_add(1, Ordering::Relaxed);
+            let wait = Wait { slot: slot.clone() };
+
+            return HttpConnectorFuture::new_boxed(Box::pin(async move {
+                match wait.await {
+                    Some(response) => response.into_http_response(),
+                    None => send(request).await,
+                }
+            }));
+        }
+
+        let slot = Arc::new(Slot::default());
+        in_flight.insert(key.clone(), slot.clone());
+
+        let (shared, max_body_size) = self.collapsing.as_ref().map_or((None, 0), |collapsing| {
+            (collapsing.shared, collapsing.max_body_size)
+        });
+        let leader = Leader {
+            collapser: self.clone(),
+            key,
+            slot,
+        };
+
+        HttpConnectorFuture::new_boxed(Box::pin(async move {
+            let transaction = match shared.map(|_| SharedResponse::lookup(&leader.key)) {
+                Some(SharedResponse::Found(response)) => {
+                    leader.complete(response.clone());
+                    return response.into_http_response();
+                }
+                Some(SharedResponse::Insert(transaction)) => Some(transaction),
+                Some(SharedResponse::Unavailable) | None => None,
+            };
+
+            // Dropping the leader without completing it sends the waiting requests on their own.
+            let response = send(request).await?;
+
+            let fits = content_length(&response).is_some_and(|length| length <= max_body_size);
+            if !fits || (transaction.is_none() && !leader.keep_if_waited_on()) {
+                return Ok(response);
+            }
+
+            let response = CollapsedResponse::read(response).await?;
+
+            if let (Some(transaction), Some(ttl)) = (transaction, shared) {
+                if response.is_success() {
+                    response.share(transaction, ttl);
+                }
+            }
+
+            leader.complete(response.clone());
+            response.into_http_response()
+        }))
+    }
+}
+
+/// Where a request that is sent on behalf of identical ones leaves its response for them.
+#[derive(Debug, Default)]
+struct Slot {
+    state: Mutex<SlotState>,
+    /// How many requests started waiting, counted while the slot is in flight.
+    waiters: AtomicUsize,
+}
+
+#[derive(Debug)]
+enum SlotState {
+    Waiting(Vec<Waker>),
+    /// The response, or nothing if the request failed.
+    Done(Option<CollapsedResponse>),
+}
+
+impl Default for SlotState {
+    fn default() -> Self {
+        SlotState::Waiting(Vec::new())
+    }
+}
+
+impl Slot {
+    /// Hands `response` to every waiting request, unless the slot was already completed.
+    fn complete(&self, response: Option<CollapsedResponse>) {
+        let mut state = self.state.lock().unwrap();
+
+        if let SlotState::Waiting(wakers) = &mut *state {
+            let wakers = mem::take(wakers);
+            *state = SlotState::Done(response);
+
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Waits for the response in a [Slot].
+struct Wait {
+    slot: Arc<Slot>,
+}
+
+impl Future for Wait {
+    type Output = Option<CollapsedResponse>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.state.lock().unwrap();
+
+        match &mut *state {
+            SlotState::Done(response) => Poll::Ready(response.clone()),
+            SlotState::Waiting(wakers) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The request that is sent on behalf of identical ones. Once it's dropped, new requests are sent again.
+struct Leader {
+    collapser: Arc<Collapser>,
+    key: String,
+    slot: Arc<Slot>,
+}
+
+impl Leader {
+    fn complete(&self, response: CollapsedResponse) {
+        self.slot.complete(Some(response));
+    }
+
+    /// Whether identical requests are waiting on this one. If none are, new ones aren't either, so the response
+    /// doesn't have to be buffered for them.
+    fn keep_if_waited_on(&self) -> bool {
+        let mut in_flight = self.collapser.in_flight.lock().unwrap();
+
+        if self.slot.waiters.load(Ordering::Relaxed) > 0 {
+            return true;
+        }
+
+        if in_flight
+            .get(&self.key)
+            .is_some_and(|slot| Arc::ptr_eq(slot, &self.slot))
+        {
+            in_flight.remove(&self.key);
+        }
+
+        false
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        let mut in_flight = self.collapser.in_flight.lock().unwrap();
+
+        if in_flight
+            .get(&self.key)
+            .is_some_and(|slot| Arc::ptr_eq(slot, &self.slot))
+        {
+            in_flight.remove(&self.key);
+        }
+
+        drop(in_flight);
+        self.slot.complete(None);
+    }
+}
+
+/// A buffered response, which can be handed to any number of requests.
+#[derive(Clone, Debug)]
+struct CollapsedResponse {
+    head: Head,
+    body: Bytes,
+}
+
+/// The status and headers of a response, kept as user metadata when it's shared through the Core Cache.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Head {
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl CollapsedResponse {
+    async fn read(mut response: HttpResponse) -> Result<Self, ConnectorError> {
+        let body = mem::replace(response.body_mut(), SdkBody::taken());
+        let body = body::collect(body).await.map_err(ConnectorError::io)?;
+
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Ok(Self {
+            head: Head {
+                status: response.status().as_u16(),
+                headers,
+            },
+            body: Bytes::from(body),
+        })
+    }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.head.status)
+    }
+
+    /// Inserts the response through `transaction`, for other instances waiting on it. Cache failures are ignored,
+    /// they'll send the request themselves.
+    fn share(&self, transaction: Transaction, ttl: Duration) {
+        let Ok(head) = serde_json::to_vec(&self.head) else {
+            return;
+        };
+
+        let insert = transaction
+            .insert(ttl)
+            .user_metadata(Bytes::from(head))
+            .execute();
+
+        if let Ok(mut stream) = insert {
+            if stream.write_all(&self.body).is_ok() {
+                let _ = stream.finish();
+            }
+        }
+    }
+
+    fn into_http_response(self) -> Result<HttpResponse, ConnectorError> {
+        let mut response = http::Response::builder().status(self.head.status);
+
+        for (name, value) in self.head.headers {
+            response = response.header(name, value);
+        }
+
+        let response = response
+            .body(SdkBody::from(self.body))
+            .map_err(ConversionError::response)?;
+
+        Ok(HttpResponse::try_from(response).map_err(ConversionError::response)?)
+    }
+}
+
+/// What other instances know about a response.
+enum SharedResponse {
+    /// Another instance got it.
+    Found(CollapsedResponse),
+    /// Nobody has it, so this instance sends the request and inserts the response for the others.
+    Insert(Transaction),
+    /// The Core Cache couldn't be used.
+    Unavailable,
+}
+
+impl SharedResponse {
+    /// Looks up the response for `key`, waiting if another instance is getting it.
+    fn lookup(key: &str) -> Self {
+        let key = CacheKey::from(format!("aws-fastly-http-client/collapse/{key}"));
+
+        let Ok(transaction) = Transaction::lookup(key).execute() else {
+            return SharedResponse::Unavailable;
+        };
+
+        if let Some(found) = transaction.found() {
+            return Self::read(found).map_or(SharedResponse::Unavailable, SharedResponse::Found);
+        }
+
+        if transaction.must_insert_or_update() {
+            return SharedResponse::Insert(transaction);
+        }
+
+        SharedResponse::Unavailable
+    }
+
+    fn read(found: Found) -> Option<CollapsedResponse> {
+        let head = serde_json::from_slice(&found.user_metadata()).ok()?;
+
+        let mut body = Vec::new();
+        found.to_stream().ok()?.read_to_end(&mut body).ok()?;
+
+        Some(CollapsedResponse {
+            head,
+            body: Bytes::from(body),
+        })
+    }
+}
+
+/// The size of the body of `response`, if it says.
+fn content_length(response: &HttpResponse) -> Option<u64> {
+    response.headers().get("content-length")?.parse().ok()
+}
+
+/// Identifies `request` by its method, URI, headers and body, leaving out what changes every time it's signed.
+fn collapse_key(request: &mut Request) -> String {
+    let body = request.take_body_bytes();
+
+    let mut headers: Vec<_> = request
+        .get_headers()
+        .filter(|(name, _)| !VOLATILE_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| format!("{name}:{}", String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(request.get_method_str());
+    hasher.update(b"\n");
+    hasher.update(request.get_url_str());
+    hasher.update(b"\n");
+    hasher.update(headers.join("\n"));
+    hasher.update(b"\n");
+    hasher.update(Sha256::digest(&body));
+
+    request.set_body(body);
+
+    format!("{:x}", hasher.finalize())
+}
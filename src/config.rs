@@ -0,0 +1,39 @@
+use aws_smithy_async::rt::sleep::SharedAsyncSleep;
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::client::behavior_version::BehaviorVersion;
+use aws_smithy_types::retry::RetryConfig;
+use aws_smithy_types::timeout::TimeoutConfig;
+use aws_types::sdk_config::Builder;
+use aws_types::SdkConfig;
+
+use crate::{FastlyHttpClient, FastlySleep, FastlyTimeSource};
+
+/// Starts an [SdkConfig] set up for Compute, so you only have to add a region and credentials before creating clients
+/// for any AWS service from it:
+///
+/// ```no_run
+/// use aws_types::region::Region;
+///
+/// let sdk_config = aws_fastly_http_client::sdk_config("dynamodb")
+///     .region(Region::from_static("us-east-1"))
+///     .build();
+///
+/// let client = aws_sdk_dynamodb::Client::new(&sdk_config);
+/// ```
+///
+/// `http_client` can be a backend or a [FastlyHttpClient]. The config sleeps with [FastlySleep] and reads the time
+/// from [FastlyTimeSource], so standard retries, which it enables, and stalled stream protection work as usual.
+/// Timeouts are disabled, leaving them to the backend's own settings, since honoring the SDK's connect and read
+/// timeouts requires dynamic backends. Any of these can be overridden on the returned builder.
+///
+/// An [SdkConfig] can't carry retry classifiers, so register
+/// [FastlyRetryClassifier](crate::FastlyRetryClassifier) on the service config if you want it.
+pub fn sdk_config(http_client: impl Into<FastlyHttpClient>) -> Builder {
+    SdkConfig::builder()
+        .http_client(http_client.into())
+        .sleep_impl(SharedAsyncSleep::new(FastlySleep))
+        .time_source(SharedTimeSource::new(FastlyTimeSource))
+        .retry_config(RetryConfig::standard())
+        .timeout_config(TimeoutConfig::disabled())
+        .behavior_version(BehaviorVersion::latest())
+}
@@ -0,0 +1,118 @@
+use aws_config::meta::region::{future, ProvideRegion};
+use aws_types::region::Region;
+use aws_types::sdk_config::Builder;
+use fastly::config_store::ConfigStore;
+use fastly::Backend;
+
+use crate::error::ConfigStoreError;
+use crate::{sdk_config, FastlyHttpClient};
+
+/// Loads the region, endpoint and backend of AWS services from a Fastly Config Store, so switching a service to
+/// another region or account is a config store edit rather than a deploy:
+///
+/// ```no_run
+/// use aws_fastly_http_client::FastlyConfigStoreLoader;
+///
+/// let sdk_config = FastlyConfigStoreLoader::new("aws")
+///     .load("dynamodb")
+///     .unwrap()
+///     .build();
+///
+/// let client = aws_sdk_dynamodb::Client::new(&sdk_config);
+/// ```
+///
+/// For a service called `dynamodb`, these keys are read, all of them optional:
+///
+/// - `dynamodb_region`, falling back to `region`: the region to use.
+/// - `dynamodb_endpoint_url`: overrides the endpoint the SDK resolves.
+/// - `dynamodb_backend`: the backend to send requests to. Without it, requests go to
+///   [dynamic backends](FastlyHttpClient::dynamic) for their endpoint.
+#[derive(Clone, Debug)]
+pub struct FastlyConfigStoreLoader {
+    store: String,
+}
+
+impl FastlyConfigStoreLoader {
+    /// Loads settings from the Config Store named `store`.
+    pub fn new(store: impl Into<String>) -> Self {
+        Self {
+            store: store.into(),
+        }
+    }
+
+    /// Starts an [SdkConfig](aws_types::SdkConfig) like [sdk_config] does, with the settings stored for `service`.
+    pub fn load(&self, service: &str) -> Result<Builder, ConfigStoreError> {
+        let store = self.open()?;
+
+        let http_client = match self.get(&store, &format!("{service}_backend"))? {
+            Some(backend) => FastlyHttpClient::from(
+                Backend::from_name(&backend)
+                    .map_err(|error| ConfigStoreError::new(&self.store, error))?,
+            ),
+            None => FastlyHttpClient::dynamic(),
+        };
+
+        let region = match self.get(&store, &format!("{service}_region"))? {
+            Some(region) => Some(region),
+            None => self.get(&store, "region")?,
+        };
+
+        let mut builder = sdk_config(http_client).region(region.map(Region::new));
+
+        if let Some(endpoint_url) = self.get(&store, &format!("{service}_endpoint_url"))? {
+            builder = builder.endpoint_url(endpoint_url);
+        }
+
+        Ok(builder)
+    }
+
+    /// Provides the region in the `region` key, for anything that takes a [ProvideRegion].
+    pub fn region_provider(&self) -> FastlyConfigStoreRegionProvider {
+        FastlyConfigStoreRegionProvider {
+            loader: self.clone(),
+            key: "region".to_string(),
+        }
+    }
+
+    fn open(&self) -> Result<ConfigStore, ConfigStoreError> {
+        ConfigStore::try_open(&self.store)
+            .map_err(|error| ConfigStoreError::new(&self.store, error))
+    }
+
+    fn get(&self, store: &ConfigStore, key: &str) -> Result<Option<String>, ConfigStoreError> {
+        store
+            .try_get(key)
+            .map_err(|error| ConfigStoreError::new(&self.store, error))
+    }
+}
+
+/// A [ProvideRegion] reading the region from a Fastly Config Store. Created with
+/// [FastlyConfigStoreLoader::region_provider]. No region is provided if the store can't be read, so a chain of providers
+/// can fall back to the next one.
+#[derive(Clone, Debug)]
+pub struct FastlyConfigStoreRegionProvider {
+    loader: FastlyConfigStoreLoader,
+    key: String,
+}
+
+impl FastlyConfigStoreRegionProvider {
+    /// Reads the region from this key instead.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+}
+
+impl ProvideRegion for FastlyConfigStoreRegionProvider {
+    fn region(&self) -> future::ProvideRegion<'_> {
+        let region = self
+            .loader
+            .open()
+            .and_then(|store| self.loader.get(&store, &self.key))
+            .ok()
+            .flatten()
+            .map(Region::new);
+
+        future::ProvideRegion::ready(region)
+    }
+}
@@ -0,0 +1,365 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aws_smithy_async::rt::sleep::AsyncSleep;
+use aws_smithy_runtime_api::client::http::{HttpConnector, HttpConnectorFuture};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::body::SdkBody;
+use fastly::http::header::CONTENT_LENGTH;
+use fastly::http::FramingHeadersMode;
+use fastly::{Backend, Body, Request, Response};
+use futures::future::{BoxFuture, Either};
+use futures::{future, FutureExt, TryFutureExt};
+
+use crate::backend::{Backends, Target, Timeouts};
+use crate::body;
+use crate::caching::CachePolicy;
+use crate::circuit::Circuits;
+use crate::collapse::Collapser;
+use crate::error::{BackendError, ConversionError, FastlyConnectorError};
+use crate::failover::{self, Failover};
+use crate::hedge::Hedging;
+use crate::limit::Limiter;
+use crate::reactor::{Reactor, ResponseFuture};
+use crate::sleep::FastlySleep;
+use crate::transport::{Transport, TransportError};
+
+#[derive(Debug)]
+pub(crate) struct FastlyHttpConnector<T: Transport> {
+    pub(crate) target: Target,
+    pub(crate) timeouts: Timeouts,
+    pub(crate) backends: Arc<Backends>,
+    pub(crate) transport: Arc<T>,
+    pub(crate) reactor: Arc<Reactor<T>>,
+    pub(crate) circuits: Arc<Circuits>,
+    pub(crate) limiter: Arc<Limiter>,
+    pub(crate) hedging: Option<Hedging>,
+    pub(crate) caching: Option<CachePolicy>,
+    pub(crate) collapser: Arc<Collapser>,
+}
+
+impl<T: Transport> Clone for FastlyHttpConnector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target.clone(),
+            timeouts: self.timeouts,
+            backends: self.backends.clone(),
+            transport: self.transport.clone(),
+            reactor: self.reactor.clone(),
+            circuits: self.circuits.clone(),
+            limiter: self.limiter.clone(),
+            hedging: self.hedging.clone(),
+            caching: self.caching.clone(),
+            collapser: self.collapser.clone(),
+        }
+    }
+}
+
+impl<T: Transport> HttpConnector for FastlyHttpConnector<T> {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let (mut request, body) = match Request::from_http_request(request) {
+            Ok(converted) => converted,
+            Err(error) => return HttpConnectorFuture::ready(Err(error.into())),
+        };
+
+        let Some(body) = body else {
+            if let Some(caching) = &self.caching {
+                caching.apply(&mut request);
+            }
+
+            if self.collapser.applies_to(&request) {
+                let connector = self.clone();
+                return self
+                    .collapser
+                    .collapse(request, move |request| connector.send(request));
+            }
+
+            return self.send(request);
+        };
+
+        let backend = match self.backends.resolve(
+            self.transport.as_ref(),
+            &self.target,
+            &request,
+            self.timeouts,
+        ) {
+            Ok(backend) => backend,
+            Err(error) => return HttpConnectorFuture::ready(Err(error)),
+        };
+
+        if let Err(error) = self.circuits.check(backend.name()) {
+            return HttpConnectorFuture::ready(Err(error.into()));
+        }
+
+        let line = RequestLine::from(&request);
+
+        let (streaming_body, pending) = match self.transport.send_streaming(request, &backend) {
+            Ok(streaming) => streaming,
+            Err(error) => {
+                self.circuits.record_failure(backend.name());
+                return HttpConnectorFuture::ready(Err(send_error(&line, error)));
+            }
+        };
+
+        let transport = self.transport.clone();
+        let reactor = self.reactor.clone();
+        let circuits = self.circuits.clone();
+
+        let response = async move {
+            body::pump(body, streaming_body, transport.as_ref()).await?;
+
+            let result = ResponseFuture::new(pending, transport, reactor).await;
+            circuits.record(backend.name(), &result);
+
+            into_http_response(result.map_err(|error| send_error(&line, error))?)
+        };
+
+        HttpConnectorFuture::new_boxed(Box::pin(response))
+    }
+}
+
+impl<T: Transport> FastlyHttpConnector<T> {
+    /// Sends a request with a buffered body to the backend of the target.
+    fn send(&self, request: Request) -> HttpConnectorFuture {
+        if let Target::Failover(failover) = &self.target {
+            return self.fail_over(request, failover.clone());
+        }
+
+        let backend = match self.backends.resolve(
+            self.transport.as_ref(),
+            &self.target,
+            &request,
+            self.timeouts,
+        ) {
+            Ok(backend) => backend,
+            Err(error) => return HttpConnectorFuture::ready(Err(error)),
+        };
+
+        if let Err(error) = self.circuits.check(backend.name()) {
+            return HttpConnectorFuture::ready(Err(error.into()));
+        }
+
+        if let Some(hedging) = &self.hedging {
+            if let Some(delay) = hedging.delay_for(&request) {
+                return self.hedge(request, backend, hedging, delay);
+            }
+        }
+
+        let line = RequestLine::from(&request);
+        let response = exchange(
+            &self.transport,
+            &self.reactor,
+            &self.circuits,
+            request,
+            backend,
+        )
+        .map_err(move |error| send_error(&line, error))
+        .and_then(|response| future::ready(into_http_response(response)));
+
+        HttpConnectorFuture::new_boxed(Box::pin(response))
+    }
+
+    /// Sends a request with a buffered body to each backend in turn, until one of them works.
+    fn fail_over(&self, mut request: Request, failover: Failover) -> HttpConnectorFuture {
+        let line = RequestLine::from(&request);
+        let timeouts = self.timeouts;
+        let backends = self.backends.clone();
+        let transport = self.transport.clone();
+        let reactor = self.reactor.clone();
+        let circuits = self.circuits.clone();
+
+        let response = async move {
+            let mut candidates = failover.candidates().into_iter().peekable();
+
+            while let Some(backend) = candidates.next() {
+                let is_last = candidates.peek().is_none();
+                let mut attempt = request.clone_with_body();
+
+                failover
+                    .prepare(&mut attempt, &backend)
+                    .map_err(|error| ConnectorError::other(error, None))?;
+
+                let backend = backends.resolve(
+                    transport.as_ref(),
+                    &Target::Backend(backend),
+                    &attempt,
+                    timeouts,
+                )?;
+
+                match circuits.check(backend.name()) {
+                    Ok(()) => {}
+                    Err(_) if !is_last => continue,
+                    Err(error) => return Err(error.into()),
+                }
+
+                let result = exchange(&transport, &reactor, &circuits, attempt, backend).await;
+
+                // Anything that isn't returned fails over to the next backend.
+                match result {
+                    Ok(response)
+                        if !is_last && failover.fails_over_on_status(response.get_status()) => {}
+                    Ok(response) => return into_http_response(response),
+                    Err(error) if !is_last && failover::fails_over_on_cause(error.cause()) => {}
+                    Err(error) => return Err(send_error(&line, error)),
+                }
+            }
+
+            Err(BackendError::new("failover", "no backends to fail over to").into())
+        };
+
+        HttpConnectorFuture::new_boxed(Box::pin(response))
+    }
+
+    /// Sends a request with a buffered body, and a duplicate of it if there's no response after `delay`.
+    fn hedge(
+        &self,
+        mut request: Request,
+        backend: Backend,
+        hedging: &Hedging,
+        delay: Duration,
+    ) -> HttpConnectorFuture {
+        let line = RequestLine::from(&request);
+        let duplicate = request.clone_with_body();
+
+        let duplicate_backend = match hedging.backend_for_duplicates() {
+            Some(other) => {
+                let other = Target::Backend(other.clone());
+                match self.backends.resolve(
+                    self.transport.as_ref(),
+                    &other,
+                    &duplicate,
+                    self.timeouts,
+                ) {
+                    Ok(other) => other,
+                    Err(error) => return HttpConnectorFuture::ready(Err(error)),
+                }
+            }
+            None => backend.clone(),
+        };
+
+        let transport = self.transport.clone();
+        let reactor = self.reactor.clone();
+        let circuits = self.circuits.clone();
+        let limiter = self.limiter.clone();
+        // The sleep is started along with the request, so the reactor knows when to stop waiting for its response.
+        let hedge_after = FastlySleep.sleep(delay);
+        let first = exchange(&transport, &reactor, &circuits, request, backend);
+
+        let response = async move {
+            let result = match future::select(first, hedge_after).await {
+                Either::Left((result, _)) => result,
+                Either::Right(((), first)) if circuits.check(duplicate_backend.name()).is_err() => {
+                    first.await
+                }
+                // The duplicate takes up a place in the concurrency limit of its own, so it's only sent if one is free.
+                Either::Right(((), first)) => match limiter.try_acquire() {
+                    None => first.await,
+                    Some(permit) => {
+                        let second = exchange(
+                            &transport,
+                            &reactor,
+                            &circuits,
+                            duplicate,
+                            duplicate_backend,
+                        )
+                        .inspect(move |_| drop(permit))
+                        .boxed();
+
+                        // Dropping the slower request abandons it, along with its place.
+                        match future::select(first, second).await {
+                            Either::Left((Err(_), other)) | Either::Right((Err(_), other)) => {
+                                other.await
+                            }
+                            Either::Left((result, _)) | Either::Right((result, _)) => result,
+                        }
+                    }
+                },
+            };
+
+            into_http_response(result.map_err(|error| send_error(&line, error))?)
+        };
+
+        HttpConnectorFuture::new_boxed(Box::pin(response))
+    }
+}
+
+/// Sends a request and waits for its response, counting the outcome towards the circuit of the backend.
+fn exchange<T: Transport>(
+    transport: &Arc<T>,
+    reactor: &Arc<Reactor<T>>,
+    circuits: &Arc<Circuits>,
+    request: Request,
+    backend: Backend,
+) -> BoxFuture<'static, Result<Response, TransportError>> {
+    match transport.send(request, &backend) {
+        Ok(pending) => {
+            let circuits = circuits.clone();
+
+            ResponseFuture::new(pending, transport.clone(), reactor.clone())
+                .inspect(move |result| circuits.record(backend.name(), result))
+                .boxed()
+        }
+        Err(error) => {
+            circuits.record_failure(backend.name());
+            future::ready(Err(error)).boxed()
+        }
+    }
+}
+
+trait FromHttpRequest: Sized {
+    /// Converts the request, returning the body separately if it has to be streamed to the backend.
+    fn from_http_request(request: HttpRequest) -> Result<(Self, Option<SdkBody>), ConversionError>;
+}
+
+impl FromHttpRequest for Request {
+    fn from_http_request(request: HttpRequest) -> Result<(Self, Option<SdkBody>), ConversionError> {
+        let (parts, body) = request
+            .try_into_http02x()
+            .map_err(ConversionError::request)?
+            .into_parts();
+
+        if let Some(bytes) = body.bytes() {
+            let request = http::Request::from_parts(parts, Body::from(bytes));
+            return Ok((fastly::Request::from(request), None));
+        }
+
+        let mut request = fastly::Request::from(http::Request::from_parts(parts, Body::new()));
+
+        // Fastly sends streaming bodies chunked unless told otherwise. The SDK sets Content-Length for sized streams
+        // and for aws-chunked uploads, where it has to match the signed payload, so keep it as is.
+        if request.contains_header(CONTENT_LENGTH) {
+            request.set_framing_headers_mode(FramingHeadersMode::ManuallyFromHeaders);
+        }
+
+        Ok((request, Some(body)))
+    }
+}
+
+fn into_http_response(response: Response) -> Result<HttpResponse, ConnectorError> {
+    let response: http::Response<Body> = response.into();
+    let to_sdk_body = |body: Body| SdkBody::from_body_0_4(body::ResponseBody::from(body));
+
+    HttpResponse::try_from(response.map(to_sdk_body))
+        .map_err(|error| ConversionError::response(error).into())
+}
+
+/// The method and URI of a request, for reporting errors about it after it was sent.
+struct RequestLine {
+    method: String,
+    uri: String,
+}
+
+impl From<&Request> for RequestLine {
+    fn from(request: &Request) -> Self {
+        Self {
+            method: request.get_method_str().to_string(),
+            uri: request.get_url_str().to_string(),
+        }
+    }
+}
+
+fn send_error(line: &RequestLine, error: TransportError) -> ConnectorError {
+    FastlyConnectorError::new(&line.method, &line.uri, error).into()
+}
@@ -0,0 +1,114 @@
+use aws_credential_types::provider::error::CredentialsError;
+use aws_credential_types::provider::{future, ProvideCredentials};
+use aws_credential_types::Credentials;
+use aws_smithy_runtime_api::box_error::BoxError;
+use fastly::secret_store::SecretStore;
+
+use crate::error::SecretStoreError;
+
+const PROVIDER_NAME: &str = "FastlySecretStore";
+
+/// Provides AWS credentials stored in a Fastly Secret Store, so they don't have to be compiled into the service:
+///
+/// ```no_run
+/// use aws_credential_types::provider::SharedCredentialsProvider;
+/// use aws_fastly_http_client::FastlySecretStoreCredentialsProvider;
+/// use aws_types::region::Region;
+///
+/// let credentials_provider = FastlySecretStoreCredentialsProvider::new("aws_credentials");
+///
+/// let sdk_config = aws_fastly_http_client::sdk_config("dynamodb")
+///     .region(Region::from_static("us-east-1"))
+///     .credentials_provider(SharedCredentialsProvider::new(credentials_provider))
+///     .build();
+/// ```
+///
+/// The secrets are read from the keys `aws_access_key_id`, `aws_secret_access_key` and `aws_session_token` unless
+/// configured otherwise, ignoring surrounding whitespace. The session token is optional. A missing store or secret is
+/// reported as credentials not being loaded, with a [SecretStoreError] as the source, so a chain of providers can fall
+/// back to the next one.
+#[derive(Clone, Debug)]
+pub struct FastlySecretStoreCredentialsProvider {
+    store: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+}
+
+impl FastlySecretStoreCredentialsProvider {
+    /// Reads credentials from the Secret Store named `store`.
+    pub fn new(store: impl Into<String>) -> Self {
+        Self {
+            store: store.into(),
+            access_key_id: "aws_access_key_id".to_string(),
+            secret_access_key: "aws_secret_access_key".to_string(),
+            session_token: "aws_session_token".to_string(),
+        }
+    }
+
+    /// Reads the access key ID from the secret with this key.
+    pub fn with_access_key_id(mut self, key: impl Into<String>) -> Self {
+        self.access_key_id = key.into();
+        self
+    }
+
+    /// Reads the secret access key from the secret with this key.
+    pub fn with_secret_access_key(mut self, key: impl Into<String>) -> Self {
+        self.secret_access_key = key.into();
+        self
+    }
+
+    /// Reads the session token from the secret with this key, if it exists.
+    pub fn with_session_token(mut self, key: impl Into<String>) -> Self {
+        self.session_token = key.into();
+        self
+    }
+
+    fn load(&self) -> Result<Credentials, SecretStoreError> {
+        let store = SecretStore::open(&self.store)
+            .map_err(|error| SecretStoreError::open(&self.store, error))?;
+
+        let access_key_id = self.require(&store, &self.access_key_id)?;
+        let secret_access_key = self.require(&store, &self.secret_access_key)?;
+        let session_token = self.read(&store, &self.session_token)?;
+
+        Ok(Credentials::new(
+            access_key_id,
+            secret_access_key,
+            session_token,
+            None,
+            PROVIDER_NAME,
+        ))
+    }
+
+    fn require(&self, store: &SecretStore, key: &str) -> Result<String, SecretStoreError> {
+        self.read(store, key)?
+            .ok_or_else(|| SecretStoreError::missing_secret(&self.store, key))
+    }
+
+    fn read(&self, store: &SecretStore, key: &str) -> Result<Option<String>, SecretStoreError> {
+        let unreadable =
+            |error: BoxError| SecretStoreError::unreadable_secret(&self.store, key, error);
+
+        let Some(secret) = store
+            .try_get(key)
+            .map_err(|error| unreadable(error.into()))?
+        else {
+            return Ok(None);
+        };
+
+        let secret = String::from_utf8(secret.plaintext().to_vec())
+            .map_err(|error| unreadable(error.into()))?;
+
+        Ok(Some(secret.trim().to_string()))
+    }
+}
+
+impl ProvideCredentials for FastlySecretStoreCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::ready(self.load().map_err(CredentialsError::from))
+    }
+}
@@ -0,0 +1,483 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use aws_credential_types::provider::error::CredentialsError;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use fastly::http::request::SendErrorCause;
+
+use crate::transport::TransportError;
+
+/// Returned as the source of a [ConnectorError] when Fastly couldn't send a request or didn't get a response for it.
+/// It keeps the details of the failure, so you can tell DNS, TLS and connection problems apart:
+///
+/// ```no_run
+/// # use aws_smithy_runtime_api::client::result::ConnectorError;
+/// use aws_fastly_http_client::FastlyConnectorError;
+/// use fastly::http::request::SendErrorCause;
+///
+/// # fn report(error: ConnectorError) {
+/// if let Ok(error) = error.into_source().downcast::<FastlyConnectorError>() {
+///     match error.cause() {
+///         SendErrorCause::DnsError { rcode, .. } => println!("DNS error {rcode:?} for {}", error.uri()),
+///         cause => println!("{cause} from backend {}", error.backend()),
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FastlyConnectorError {
+    method: String,
+    uri: String,
+    error: TransportError,
+}
+
+impl FastlyConnectorError {
+    pub(crate) fn new(
+        method: impl Into<String>,
+        uri: impl Into<String>,
+        error: TransportError,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            uri: uri.into(),
+            error,
+        }
+    }
+
+    /// Why the request failed.
+    pub fn cause(&self) -> &SendErrorCause {
+        self.error.cause()
+    }
+
+    /// The name of the backend the request was sent to.
+    pub fn backend(&self) -> &str {
+        self.error.backend()
+    }
+
+    /// The method of the request.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// The URI of the request.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+}
+
+impl Display for FastlyConnectorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} request to {} through backend {} failed: {}",
+            self.method,
+            self.uri,
+            self.error.backend(),
+            self.error.cause()
+        )
+    }
+}
+
+impl Error for FastlyConnectorError {}
+
+impl From<FastlyConnectorError> for ConnectorError {
+    fn from(error: FastlyConnectorError) -> Self {
+        let error = Box::new(error);
+
+        match error.cause() {
+            SendErrorCause::BufferSize(_)
+            | SendErrorCause::DnsError { .. }
+            | SendErrorCause::ConnectionRefused
+            | SendErrorCause::ConnectionTerminated
+            | SendErrorCause::ConnectionLimitReached
+            | SendErrorCause::TlsProtocolError
+            | SendErrorCause::TlsAlertReceived { .. }
+            | SendErrorCause::TlsConfigurationError
+            | SendErrorCause::HttpIncompleteResponse
+            | SendErrorCause::HttpResponseHeaderSectionTooLarge
+            | SendErrorCause::HttpResponseBodyTooLarge
+            | SendErrorCause::HttpProtocolError => ConnectorError::io(error),
+            SendErrorCause::DnsTimeout
+            | SendErrorCause::ConnectionTimeout
+            | SendErrorCause::HttpResponseTimeout => ConnectorError::timeout(error),
+            _ => ConnectorError::other(error, None),
+        }
+    }
+}
+
+/// Returned as the source of a [ConnectorError] when the backend a request should be sent to can't be set up, for
+/// example because dynamic backends aren't enabled for the service.
+#[derive(Debug)]
+pub struct BackendError {
+    backend: String,
+    source: BoxError,
+}
+
+impl BackendError {
+    pub(crate) fn new(backend: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self {
+            backend: backend.into(),
+            source: source.into(),
+        }
+    }
+
+    /// The name of the backend that couldn't be set up.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to set up backend {}", self.backend)
+    }
+}
+
+impl Error for BackendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl From<BackendError> for ConnectorError {
+    fn from(error: BackendError) -> Self {
+        ConnectorError::other(Box::new(error), None)
+    }
+}
+
+/// Returned as the source of a [ConnectorError] when a client with a routing table is asked to send a request to a
+/// host none of its routes match.
+#[derive(Debug)]
+pub struct NoRouteError {
+    host: String,
+}
+
+impl NoRouteError {
+    pub(crate) fn new(host: impl Into<String>) -> Self {
+        Self { host: host.into() }
+    }
+
+    /// The host of the request that couldn't be routed.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+impl Display for NoRouteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no backend is routed for host {}", self.host)
+    }
+}
+
+impl Error for NoRouteError {}
+
+impl From<NoRouteError> for ConnectorError {
+    fn from(error: NoRouteError) -> Self {
+        ConnectorError::other(Box::new(error), None)
+    }
+}
+
+/// Returned as the source of a [ConnectorError] when a request can't be converted into a Fastly request, or a backend
+/// response can't be converted into an SDK response. This happens for things like header values the other side can't
+/// represent.
+#[derive(Debug)]
+pub struct ConversionError {
+    kind: ConversionErrorKind,
+    source: BoxError,
+}
+
+/// What a [ConversionError] failed to convert.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ConversionErrorKind {
+    /// An SDK request couldn't be converted into a Fastly request.
+    Request,
+    /// A Fastly response couldn't be converted into an SDK response.
+    Response,
+}
+
+impl ConversionError {
+    pub(crate) fn request(source: impl Into<BoxError>) -> Self {
+        Self {
+            kind: ConversionErrorKind::Request,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn response(source: impl Into<BoxError>) -> Self {
+        Self {
+            kind: ConversionErrorKind::Response,
+            source: source.into(),
+        }
+    }
+
+    /// Whether the request or the response failed to convert.
+    pub fn kind(&self) -> ConversionErrorKind {
+        self.kind
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ConversionErrorKind::Request => {
+                write!(f, "failed to convert SDK request into a Fastly request")
+            }
+            ConversionErrorKind::Response => {
+                write!(f, "failed to convert Fastly response into an SDK response")
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl From<ConversionError> for ConnectorError {
+    fn from(error: ConversionError) -> Self {
+        ConnectorError::other(Box::new(error), None)
+    }
+}
+
+/// Returned as the source of a [CredentialsError] when a
+/// [FastlySecretStoreCredentialsProvider](crate::FastlySecretStoreCredentialsProvider) can't read credentials from its
+/// Secret Store.
+#[derive(Debug)]
+pub struct SecretStoreError {
+    store: String,
+    kind: SecretStoreErrorKind,
+    source: Option<BoxError>,
+}
+
+/// What a [SecretStoreError] failed to read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SecretStoreErrorKind {
+    /// The Secret Store doesn't exist or couldn't be opened.
+    Store,
+    /// The secret with this key isn't in the store.
+    MissingSecret(String),
+    /// The secret with this key couldn't be looked up, or isn't valid UTF-8.
+    UnreadableSecret(String),
+}
+
+impl SecretStoreError {
+    pub(crate) fn open(store: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self {
+            store: store.into(),
+            kind: SecretStoreErrorKind::Store,
+            source: Some(source.into()),
+        }
+    }
+
+    pub(crate) fn missing_secret(store: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            store: store.into(),
+            kind: SecretStoreErrorKind::MissingSecret(key.into()),
+            source: None,
+        }
+    }
+
+    pub(crate) fn unreadable_secret(
+        store: impl Into<String>,
+        key: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self {
+            store: store.into(),
+            kind: SecretStoreErrorKind::UnreadableSecret(key.into()),
+            source: Some(source.into()),
+        }
+    }
+
+    /// The name of the Secret Store.
+    pub fn store(&self) -> &str {
+        &self.store
+    }
+
+    /// What couldn't be read.
+    pub fn kind(&self) -> &SecretStoreErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for SecretStoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            SecretStoreErrorKind::Store => write!(f, "failed to open secret store {}", self.store),
+            SecretStoreErrorKind::MissingSecret(key) => {
+                write!(
+                    f,
+                    "secret {key} is missing from secret store {}",
+                    self.store
+                )
+            }
+            SecretStoreErrorKind::UnreadableSecret(key) => {
+                write!(
+                    f,
+                    "failed to read secret {key} from secret store {}",
+                    self.store
+                )
+            }
+        }
+    }
+}
+
+impl Error for SecretStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
+impl From<SecretStoreError> for CredentialsError {
+    fn from(error: SecretStoreError) -> Self {
+        match error.kind {
+            // Nothing to load, so the next provider in a chain gets a chance.
+            SecretStoreErrorKind::Store | SecretStoreErrorKind::MissingSecret(_) => {
+                CredentialsError::not_loaded(error)
+            }
+            SecretStoreErrorKind::UnreadableSecret(_) => CredentialsError::provider_error(error),
+        }
+    }
+}
+
+/// Returned by [FastlyConfigStoreLoader](crate::FastlyConfigStoreLoader) when its Config Store can't be read, or names
+/// a backend that doesn't exist.
+#[derive(Debug)]
+pub struct ConfigStoreError {
+    store: String,
+    source: BoxError,
+}
+
+impl ConfigStoreError {
+    pub(crate) fn new(store: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self {
+            store: store.into(),
+            source: source.into(),
+        }
+    }
+
+    /// The name of the Config Store.
+    pub fn store(&self) -> &str {
+        &self.store
+    }
+}
+
+impl Display for ConfigStoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to load settings from config store {}",
+            self.store
+        )
+    }
+}
+
+impl Error for ConfigStoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Returned as the source of a [ConnectorError] when a request isn't sent because the
+/// [CircuitBreaker](crate::CircuitBreaker) of its backend is open.
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    backend: String,
+}
+
+impl CircuitOpenError {
+    pub(crate) fn new(backend: impl Into<String>) -> Self {
+        Self {
+            backend: backend.into(),
+        }
+    }
+
+    /// The name of the backend the request would have been sent to.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+}
+
+impl Display for CircuitOpenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "circuit of backend {} is open", self.backend)
+    }
+}
+
+impl Error for CircuitOpenError {}
+
+impl From<CircuitOpenError> for ConnectorError {
+    fn from(error: CircuitOpenError) -> Self {
+        ConnectorError::other(Box::new(error), None)
+    }
+}
+
+/// Returned by [GetItemCache::invalidate](crate::GetItemCache::invalidate) when the cached results for an item couldn't
+/// be purged.
+#[derive(Debug)]
+pub struct ItemCacheError {
+    table: String,
+    source: BoxError,
+}
+
+impl ItemCacheError {
+    pub(crate) fn new(table: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self {
+            table: table.into(),
+            source: source.into(),
+        }
+    }
+
+    /// The name of the table of the item.
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+}
+
+impl Display for ItemCacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to purge cached item of table {}", self.table)
+    }
+}
+
+impl Error for ItemCacheError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Returned by [GeoRegions::region](crate::GeoRegions::region) for a region whose location isn't known.
+#[derive(Debug)]
+pub struct UnknownRegionError {
+    region: String,
+}
+
+impl UnknownRegionError {
+    pub(crate) fn new(region: impl Into<String>) -> Self {
+        Self {
+            region: region.into(),
+        }
+    }
+
+    /// The region whose location isn't known.
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+}
+
+impl Display for UnknownRegionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "location of region {} isn't known, add it with region_at",
+            self.region
+        )
+    }
+}
+
+impl Error for UnknownRegionError {}
@@ -0,0 +1,91 @@
+use std::future::Future;
+
+#[cfg(not(feature = "tokio"))]
+use std::{
+    pin::pin,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+#[cfg(not(feature = "tokio"))]
+use crate::reactor;
+
+#[cfg(feature = "tokio")]
+use {
+    crate::reactor,
+    futures::future::{self, Either},
+    std::pin::pin,
+};
+
+/// Runs a future to completion on the current thread. This is all the runtime the AWS SDK needs on Compute: the
+/// future is polled until it can't make progress, at which point the thread blocks on the Fastly host until one of the
+/// requests of any client completes and polling resumes. If it's waiting for a [FastlySleep](crate::FastlySleep)
+/// instead, the thread sleeps until it's due. Requests made through [FastlyHttpClient](crate::FastlyHttpClient) only
+/// make progress while driven by `block_on`.
+///
+/// ```no_run
+/// use fastly::Response;
+///
+/// fn main() {
+///     let response = aws_fastly_http_client::block_on(async {
+///         // Call AWS using a client configured with `FastlyHttpClient`.
+///         Response::new()
+///     });
+///
+///     response.send_to_client();
+/// }
+/// ```
+///
+/// With the `tokio` feature enabled, the future is driven by a current thread Tokio runtime instead, so that Tokio
+/// timers and other utilities are available to it. Requests are then polled once a millisecond while any are in
+/// flight, since the host can't wait on them and Tokio's timers together.
+#[cfg(not(feature = "tokio"))]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let signal = Arc::new(Signal::default());
+    let waker = Waker::from(signal.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        if !signal.woken.swap(false, Ordering::AcqRel) && !reactor::idle() {
+            panic!("future is pending without anything left to wake it");
+        }
+    }
+}
+
+/// Runs a future to completion on a current thread Tokio runtime.
+#[cfg(feature = "tokio")]
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("failed to build Tokio runtime")
+        .block_on(async {
+            match future::select(pin!(future), pin!(reactor::drive())).await {
+                Either::Left((output, _)) => output,
+                Either::Right((never, _)) => match never {},
+            }
+        })
+}
+
+#[cfg(not(feature = "tokio"))]
+#[derive(Default)]
+struct Signal {
+    woken: AtomicBool,
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Wake for Signal {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use fastly::backend::BackendHealth;
+use fastly::convert::ToBackend;
+use fastly::http::request::SendErrorCause;
+use fastly::http::StatusCode;
+use fastly::{Backend, Request};
+
+type Resign = dyn Fn(&mut Request, &Backend) -> Result<(), BoxError> + Send + Sync;
+
+/// An ordered list of backends for a [FastlyHttpClient](crate::FastlyHttpClient) to fail over between, such as replicas
+/// of a service in several regions:
+///
+/// ```no_run
+/// use aws_fastly_http_client::{Failover, FastlyHttpClient};
+///
+/// let failover = Failover::new()
+///     .backend("dynamodb_us_east_1")
+///     .backend("dynamodb_us_west_2");
+///
+/// let http_client = FastlyHttpClient::failover(failover);
+/// ```
+///
+/// Backends Fastly's health checks report as unhealthy are skipped, unless all of them are. When a request can't
+/// connect to a backend, or gets one of the failover statuses, which are 502, 503 and 504 by default, it's sent to the
+/// next backend, and the last backend's outcome is returned as is.
+///
+/// Requests are signed for the first backend. If the others are in different regions, register a hook with
+/// [Failover::resign] to sign the request again before it's sent to them. Requests with streaming bodies can't be sent
+/// twice, so they only go to the first backend, even if it's unhealthy.
+#[derive(Clone)]
+pub struct Failover {
+    backends: Vec<Backend>,
+    statuses: Vec<StatusCode>,
+    resign: Option<Arc<Resign>>,
+}
+
+impl Default for Failover {
+    fn default() -> Self {
+        Self {
+            backends: Vec::new(),
+            statuses: vec![
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            resign: None,
+        }
+    }
+}
+
+impl Failover {
+    /// Creates a list without any backends.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails over to `backend` after the backends added before it.
+    pub fn backend(mut self, backend: impl ToBackend) -> Self {
+        self.backends.push(backend.into_owned());
+        self
+    }
+
+    /// Fails over on these response statuses instead of the defaults.
+    pub fn statuses(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Calls `resign` on a request before it's sent to any backend but the first, to sign it for that backend's
+    /// region. Failing to sign it fails the request.
+    pub fn resign(
+        mut self,
+        resign: impl Fn(&mut Request, &Backend) -> Result<(), BoxError> + Send + Sync + 'static,
+    ) -> Self {
+        self.resign = Some(Arc::new(resign));
+        self
+    }
+
+    /// The backends to try, in order.
+    pub(crate) fn candidates(&self) -> Vec<Backend> {
+        let healthy: Vec<_> = self
+            .backends
+            .iter()
+            .filter(|backend| !matches!(backend.is_healthy(), Ok(BackendHealth::Unhealthy)))
+            .cloned()
+            .collect();
+
+        if healthy.is_empty() {
+            self.backends.clone()
+        } else {
+            healthy
+        }
+    }
+
+    /// The first backend added, which requests are signed for. Requests with streaming bodies only go there, whatever
+    /// its health, since they can't be signed again.
+    pub(crate) fn first(&self) -> Option<&Backend> {
+        self.backends.first()
+    }
+
+    /// Prepares a request for `backend`, signing it again unless that's the first backend added, which requests are
+    /// signed for. The first backend that's tried isn't necessarily that one, if it's unhealthy.
+    pub(crate) fn prepare(&self, request: &mut Request, backend: &Backend) -> Result<(), BoxError> {
+        let is_first = self
+            .first()
+            .is_some_and(|first| first.name() == backend.name());
+
+        match &self.resign {
+            Some(resign) if !is_first => resign(request, backend),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn fails_over_on_status(&self, status: StatusCode) -> bool {
+        self.statuses.contains(&status)
+    }
+}
+
+impl Debug for Failover {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Failover")
+            .field("backends", &self.backends)
+            .field("statuses", &self.statuses)
+            .field("resign", &self.resign.is_some())
+            .finish()
+    }
+}
+
+/// Whether a request failed because the backend couldn't be reached, so it's safe to send it elsewhere.
+pub(crate) fn fails_over_on_cause(cause: &SendErrorCause) -> bool {
+    matches!(
+        cause,
+        SendErrorCause::DnsError { .. }
+            | SendErrorCause::DnsTimeout
+            | SendErrorCause::ConnectionRefused
+            | SendErrorCause::ConnectionTimeout
+            | SendErrorCause::ConnectionLimitReached
+            | SendErrorCause::TlsProtocolError
+            | SendErrorCause::TlsAlertReceived { .. }
+            | SendErrorCause::TlsConfigurationError
+    )
+}
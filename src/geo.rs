@@ -0,0 +1,224 @@
+use aws_types::region::Region;
+use fastly::convert::ToBackend;
+use fastly::geo::geo_lookup;
+use fastly::{Backend, Request};
+
+use crate::error::UnknownRegionError;
+use crate::FastlyHttpClient;
+
+/// Approximate locations of AWS regions, as latitude and longitude.
+const LOCATIONS: &[(&str, f64, f64)] = &[
+    ("af-south-1", -33.92, 18.42),
+    ("ap-east-1", 22.32, 114.17),
+    ("ap-northeast-1", 35.68, 139.69),
+    ("ap-northeast-2", 37.57, 126.98),
+    ("ap-northeast-3", 34.69, 135.50),
+    ("ap-south-1", 19.08, 72.88),
+    ("ap-south-2", 17.39, 78.49),
+    ("ap-southeast-1", 1.35, 103.82),
+    ("ap-southeast-2", -33.87, 151.21),
+    ("ap-southeast-3", -6.21, 106.85),
+    ("ap-southeast-4", -37.81, 144.96),
+    ("ca-central-1", 45.50, -73.57),
+    ("ca-west-1", 51.05, -114.07),
+    ("eu-central-1", 50.11, 8.68),
+    ("eu-central-2", 47.37, 8.54),
+    ("eu-north-1", 59.33, 18.07),
+    ("eu-south-1", 45.46, 9.19),
+    ("eu-south-2", 41.65, -0.88),
+    ("eu-west-1", 53.35, -6.26),
+    ("eu-west-2", 51.51, -0.13),
+    ("eu-west-3", 48.86, 2.35),
+    ("il-central-1", 32.09, 34.78),
+    ("me-central-1", 24.45, 54.38),
+    ("me-south-1", 26.07, 50.56),
+    ("sa-east-1", -23.55, -46.63),
+    ("us-east-1", 39.04, -77.49),
+    ("us-east-2", 39.96, -83.00),
+    ("us-west-1", 37.77, -122.42),
+    ("us-west-2", 45.84, -119.70),
+];
+
+/// Mean radius of the earth in kilometers.
+const EARTH_RADIUS: f64 = 6371.0;
+
+/// The regions a multi-region deployment, like a DynamoDB global table, runs in, with the backend for each, so every
+/// request can go to the region closest to the client:
+///
+/// ```no_run
+/// use aws_fastly_http_client::GeoRegions;
+/// use fastly::Request;
+///
+/// let regions = GeoRegions::new()
+///     .region("us-east-1", "dynamodb_us_east_1")
+///     .unwrap()
+///     .region("eu-west-1", "dynamodb_eu_west_1")
+///     .unwrap()
+///     .region("ap-southeast-2", "dynamodb_ap_southeast_2")
+///     .unwrap();
+///
+/// let nearest = regions.nearest_to_client(&Request::from_client()).unwrap();
+///
+/// let sdk_config = aws_fastly_http_client::sdk_config(nearest.http_client())
+///     .region(nearest.region())
+///     .build();
+/// ```
+///
+/// Distances are measured from the location `fastly::geo` reports for the client IP. When a client can't be located,
+/// they're measured from the Fastly POP that received the request instead, and only if that can't be located either is
+/// the first region added used.
+#[derive(Clone, Debug, Default)]
+pub struct GeoRegions {
+    regions: Vec<RegionBackend>,
+}
+
+impl GeoRegions {
+    /// Creates a table without any regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an AWS region, served by `backend`. Fails if the location of the region isn't known, use
+    /// [GeoRegions::region_at] for those.
+    pub fn region(self, region: &str, backend: impl ToBackend) -> Result<Self, UnknownRegionError> {
+        let Some(&(_, latitude, longitude)) = LOCATIONS.iter().find(|(name, ..)| *name == region)
+        else {
+            return Err(UnknownRegionError::new(region));
+        };
+
+        Ok(self.region_at(region, backend, latitude, longitude))
+    }
+
+    /// Adds a region at the given latitude and longitude, served by `backend`.
+    pub fn region_at(
+        mut self,
+        region: impl Into<String>,
+        backend: impl ToBackend,
+        latitude: f64,
+        longitude: f64,
+    ) -> Self {
+        self.regions.push(RegionBackend {
+            region: Region::new(region.into()),
+            backend: backend.into_owned(),
+            latitude,
+            longitude,
+        });
+        self
+    }
+
+    /// The region closest to the given latitude and longitude, if there are any regions.
+    pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<&RegionBackend> {
+        self.regions.iter().min_by(|a, b| {
+            let a = a.distance(latitude, longitude);
+            let b = b.distance(latitude, longitude);
+            a.total_cmp(&b)
+        })
+    }
+
+    /// The region closest to the client that sent `request`, if there are any regions.
+    pub fn nearest_to_client(&self, request: &Request) -> Option<&RegionBackend> {
+        let located = request
+            .get_client_ip_addr()
+            .and_then(geo_lookup)
+            .or_else(|| request.get_server_ip_addr().and_then(geo_lookup));
+
+        match located {
+            Some(geo) => self.nearest(geo.latitude(), geo.longitude()),
+            None => self.regions.first(),
+        }
+    }
+}
+
+/// A region in [GeoRegions], with the backend serving it.
+#[derive(Clone, Debug)]
+pub struct RegionBackend {
+    region: Region,
+    backend: Backend,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl RegionBackend {
+    /// The AWS region.
+    pub fn region(&self) -> Region {
+        self.region.clone()
+    }
+
+    /// The backend serving the region.
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// A client sending requests to the backend serving the region.
+    pub fn http_client(&self) -> FastlyHttpClient {
+        FastlyHttpClient::from(self.backend.clone())
+    }
+
+    /// The great-circle distance to a location, in kilometers.
+    fn distance(&self, latitude: f64, longitude: f64) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), latitude.to_radians());
+        let delta_lat = lat2 - lat1;
+        let delta_lon = (longitude - self.longitude).to_radians();
+
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn region_at(latitude: f64, longitude: f64) -> RegionBackend {
+        GeoRegions::new()
+            .region_at("here", "backend", latitude, longitude)
+            .regions
+            .remove(0)
+    }
+
+    #[test]
+    fn measures_great_circle_distances() {
+        let dublin = region_at(53.35, -6.26);
+
+        assert_eq!(dublin.distance(53.35, -6.26), 0.0);
+        // Paris and northern Virginia.
+        assert!((dublin.distance(48.86, 2.35) - 780.5).abs() < 1.0);
+        assert!((dublin.distance(39.04, -77.49) - 5461.5).abs() < 1.0);
+        // Half way around the world.
+        assert!((region_at(0.0, 0.0).distance(0.0, 180.0) - EARTH_RADIUS * PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn picks_the_nearest_region() {
+        let regions = GeoRegions::new()
+            .region("us-east-1", "dynamodb_us_east_1")
+            .unwrap()
+            .region("eu-west-1", "dynamodb_eu_west_1")
+            .unwrap()
+            .region_at("eu-local-1", "dynamodb_eu_local_1", 50.11, 8.68);
+
+        let nearest = |latitude, longitude| {
+            let nearest = regions.nearest(latitude, longitude).unwrap();
+            nearest.region().to_string()
+        };
+
+        // Berlin, New York and the west of Ireland.
+        assert_eq!(nearest(52.52, 13.40), "eu-local-1");
+        assert_eq!(nearest(40.71, -74.01), "us-east-1");
+        assert_eq!(nearest(53.0, -8.0), "eu-west-1");
+        assert!(GeoRegions::new().nearest(0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn rejects_regions_without_a_known_location() {
+        let error = GeoRegions::new()
+            .region("xx-nowhere-1", "dynamodb_nowhere")
+            .unwrap_err();
+
+        assert_eq!(error.region(), "xx-nowhere-1");
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use fastly::convert::ToBackend;
+use fastly::{Backend, Request};
+
+use crate::classify;
+
+type Policy = dyn Fn(&Request) -> bool + Send + Sync;
+
+/// Sends a duplicate of a request when the backend is slow to respond, and takes whichever response comes first, to
+/// cut tail latency. Enable it with [FastlyHttpClient::with_hedging](crate::FastlyHttpClient::with_hedging):
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aws_fastly_http_client::{FastlyHttpClient, Hedging};
+///
+/// let hedging = Hedging::new(Duration::from_millis(50)).backend("dynamodb_replica");
+/// let http_client = FastlyHttpClient::from("dynamodb").with_hedging(hedging);
+/// ```
+///
+/// Only requests the policy allows are hedged. By default, that's `GET` and `HEAD` requests, and DynamoDB `GetItem`,
+/// `BatchGetItem`, `Query` and `Scan` calls. Use [Hedging::when] for anything else, but only for requests that are safe
+/// to send twice. The duplicate goes to the same backend unless another one is set. If the first response is a failure
+/// to send, the other request is waited for instead, otherwise it's abandoned. Requests with streaming bodies are never
+/// hedged.
+///
+/// The delay is measured with [FastlySleep](crate::FastlySleep). The duplicate takes up a place in the
+/// [concurrency limit](crate::FastlyHttpClient::with_max_in_flight) of its own, so it's only sent if there's one free
+/// and nobody is queued for it.
+#[derive(Clone)]
+pub struct Hedging {
+    delay: Duration,
+    backend: Option<Backend>,
+    policy: Arc<Policy>,
+}
+
+impl Hedging {
+    /// Hedges requests that haven't got a response after `delay`.
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            backend: None,
+            policy: Arc::new(classify::is_read),
+        }
+    }
+
+    /// Sends the duplicates to `backend`.
+    pub fn backend(mut self, backend: impl ToBackend) -> Self {
+        self.backend = Some(backend.into_owned());
+        self
+    }
+
+    /// Only hedges requests `policy` returns true for, instead of the default policy.
+    pub fn when(mut self, policy: impl Fn(&Request) -> bool + Send + Sync + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// How long to wait before hedging `request`, if it can be hedged.
+    pub(crate) fn delay_for(&self, request: &Request) -> Option<Duration> {
+        (self.policy)(request).then_some(self.delay)
+    }
+
+    /// The backend to send duplicates to, if not the original one.
+    pub(crate) fn backend_for_duplicates(&self) -> Option<&Backend> {
+        self.backend.as_ref()
+    }
+}
+
+impl Debug for Hedging {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hedging")
+            .field("delay", &self.delay)
+            .field("backend", &self.backend)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use fastly::backend::BackendBuilder;
+use fastly::http::request::SendErrorCause;
+use fastly::{Backend, Request, Response};
+
+use crate::transport::{Transport, TransportError, TransportPoll};
+
+/// How often requests are polled while they're waited on.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A [Transport] that serves scripted responses instead of sending requests anywhere, so code using
+/// [FastlyHttpClient](crate::FastlyHttpClient) can be tested without backends, or dynamic backends, being set up for
+/// it. Every request takes the next scripted outcome, in the order they were scripted, and is recorded so you can make
+/// assertions about it:
+///
+/// ```no_run
+/// use aws_fastly_http_client::{FastlyHttpClient, InMemoryTransport};
+/// use fastly::http::request::SendErrorCause;
+/// use fastly::Response;
+///
+/// let transport = InMemoryTransport::new();
+/// transport
+///     .respond(Response::from_body(r#"{"Item":{}}"#))
+///     .fail_after(3, SendErrorCause::ConnectionRefused);
+///
+/// let http_client = FastlyHttpClient::from("dynamodb").with_transport(transport.clone());
+/// // Make some calls to AWS with `http_client`.
+///
+/// assert_eq!(transport.requests().len(), 2);
+/// ```
+///
+/// Requests and responses still live in the Fastly host, so tests using it run in Viceroy, with
+/// `cargo test --target wasm32-wasi`. So do the features that ask the host about backends or use its cache: health
+/// checks for [Failover](crate::Failover), shared circuits and cached responses.
+///
+/// Delays are counted in polls: a request with a delay of 3 is still pending the first 3 times it's polled. Waiting on
+/// requests polls them once a millisecond until one of them completes, so a request scripted with a delay of
+/// `u32::MAX` never does, and one with a delay of 3 only completes after any sleep due within 3 milliseconds.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryTransport {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    script: VecDeque<(u32, Result<Response, SendErrorCause>)>,
+    requests: Vec<SentRequest>,
+    pending: usize,
+    peak_pending: usize,
+}
+
+impl InMemoryTransport {
+    /// Creates a transport with nothing scripted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers the next request with `response`.
+    pub fn respond(&self, response: Response) -> &Self {
+        self.respond_after(0, response)
+    }
+
+    /// Answers the next request with `response`, after it has been polled `polls` times.
+    pub fn respond_after(&self, polls: u32, response: Response) -> &Self {
+        self.push(polls, Ok(response))
+    }
+
+    /// Fails the next request with `cause`.
+    pub fn fail(&self, cause: SendErrorCause) -> &Self {
+        self.fail_after(0, cause)
+    }
+
+    /// Fails the next request with `cause`, after it has been polled `polls` times.
+    pub fn fail_after(&self, polls: u32, cause: SendErrorCause) -> &Self {
+        self.push(polls, Err(cause))
+    }
+
+    /// The requests sent so far, in the order they were sent.
+    pub fn requests(&self) -> Vec<SentRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// The most requests that were sent and hadn't completed or been dropped yet, at once.
+    pub fn peak_pending(&self) -> usize {
+        self.state.lock().unwrap().peak_pending
+    }
+
+    fn push(&self, polls: u32, outcome: Result<Response, SendErrorCause>) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .script
+            .push_back((polls, outcome));
+        self
+    }
+
+    fn next(&self, mut request: Request, backend: &Backend) -> (InMemoryBody, InMemoryPending) {
+        let mut state = self.state.lock().unwrap();
+
+        let Some((delay, outcome)) = state.script.pop_front() else {
+            panic!(
+                "no response scripted for {} {}",
+                request.get_method_str(),
+                request.get_url_str()
+            );
+        };
+
+        let body = InMemoryBody {
+            body: Arc::new(Mutex::new(request.take_body().into_bytes())),
+        };
+
+        state.requests.push(SentRequest {
+            method: request.get_method_str().to_string(),
+            url: request.get_url_str().to_string(),
+            headers: request
+                .get_headers()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect(),
+            backend: backend.name().to_string(),
+            body: body.body.clone(),
+        });
+
+        let outcome = outcome.map_err(|cause| TransportError::new(backend.name(), cause));
+
+        state.pending += 1;
+        state.peak_pending = state.peak_pending.max(state.pending);
+
+        let counted = Counted {
+            state: self.state.clone(),
+        };
+
+        (
+            body,
+            InMemoryPending {
+                delay,
+                outcome,
+                _counted: counted,
+            },
+        )
+    }
+}
+
+impl Transport for InMemoryTransport {
+    type Pending = InMemoryPending;
+    type StreamingBody = InMemoryBody;
+
+    fn send(&self, request: Request, backend: &Backend) -> Result<InMemoryPending, TransportError> {
+        Ok(self.next(request, backend).1)
+    }
+
+    fn send_streaming(
+        &self,
+        request: Request,
+        backend: &Backend,
+    ) -> Result<(InMemoryBody, InMemoryPending), TransportError> {
+        Ok(self.next(request, backend))
+    }
+
+    fn finish(&self, _: InMemoryBody) -> Result<(), BoxError> {
+        Ok(())
+    }
+
+    /// Nothing is sent anywhere, so dynamic backends aren't registered: requests are recorded with the name the
+    /// backend would have had.
+    fn register_backend(
+        &self,
+        name: &str,
+        _: impl FnOnce(&str) -> BackendBuilder,
+    ) -> Result<Backend, BoxError> {
+        Ok(Backend::from_name(name)?)
+    }
+
+    fn poll(&self, mut pending: InMemoryPending) -> TransportPoll<InMemoryPending> {
+        if pending.delay == 0 {
+            return TransportPoll::Done(pending.outcome);
+        }
+
+        pending.delay -= 1;
+        TransportPoll::Pending(pending)
+    }
+
+    fn wait(&self, mut pending: InMemoryPending) -> Result<Response, TransportError> {
+        loop {
+            match self.poll(pending) {
+                TransportPoll::Done(result) => return result,
+                TransportPoll::Pending(still_pending) => pending = still_pending,
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn select(
+        &self,
+        mut pending: Vec<InMemoryPending>,
+    ) -> (
+        usize,
+        Result<Response, TransportError>,
+        Vec<InMemoryPending>,
+    ) {
+        assert!(
+            !pending.is_empty(),
+            "select called without pending requests"
+        );
+
+        loop {
+            let mut done = None;
+            let mut remaining = Vec::with_capacity(pending.len());
+
+            for (index, request) in pending.into_iter().enumerate() {
+                if done.is_some() {
+                    remaining.push(request);
+                    continue;
+                }
+
+                match self.poll(request) {
+                    TransportPoll::Done(result) => done = Some((index, result)),
+                    TransportPoll::Pending(request) => remaining.push(request),
+                }
+            }
+
+            if let Some((index, result)) = done {
+                return (index, result, remaining);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+            pending = remaining;
+        }
+    }
+}
+
+/// A request in flight on an [InMemoryTransport].
+#[derive(Debug)]
+pub struct InMemoryPending {
+    delay: u32,
+    outcome: Result<Response, TransportError>,
+    _counted: Counted,
+}
+
+/// Counts a request as pending on its transport until it completes or is dropped.
+#[derive(Debug)]
+struct Counted {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().pending -= 1;
+    }
+}
+
+/// The body of a request streamed to an [InMemoryTransport].
+#[derive(Debug)]
+pub struct InMemoryBody {
+    body: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Write for InMemoryBody {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.body.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A request received by an [InMemoryTransport].
+#[derive(Clone, Debug)]
+pub struct SentRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    backend: String,
+    body: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SentRequest {
+    /// The request method.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// The full request URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The request headers, in the order they were sent.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// The first value of a header, if it was sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The name of the backend the request was sent to.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// The request body, including anything streamed so far.
+    pub fn body(&self) -> Vec<u8> {
+        self.body.lock().unwrap().clone()
+    }
+}
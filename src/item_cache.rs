@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::mem;
+use std::time::Duration;
+
+use aws_smithy_runtime_api::client::http::{
+    HttpConnector, HttpConnectorFuture, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::body::SdkBody;
+use fastly::cache::core::{self, CacheKey};
+use fastly::http::purge;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::body;
+use crate::error::{ConversionError, ItemCacheError};
+
+const GET_ITEM: &str = "DynamoDB_20120810.GetItem";
+
+/// Serves DynamoDB `GetItem` results from the Core Cache of the POP, and caches the ones that have to be read from
+/// DynamoDB. DynamoDB requests are all `POST`s, so HTTP caching doesn't apply to them. Enable it with
+/// [FastlyHttpClient::with_item_cache](crate::FastlyHttpClient::with_item_cache):
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use aws_fastly_http_client::{FastlyHttpClient, GetItemCache};
+///
+/// let http_client = FastlyHttpClient::from("dynamodb").with_item_cache(GetItemCache::new(Duration::from_secs(30)));
+/// ```
+///
+/// Results are cached by endpoint, table and key, and by the rest of the request, such as its projection expression.
+/// Strongly consistent reads always go to DynamoDB. Items that don't exist are cached too, so they keep not existing
+/// until they expire or are invalidated.
+///
+/// Results are shared by every request for the same item, whatever credentials signed it, so only cache tables every
+/// caller of the client may read.
+#[derive(Clone, Debug)]
+pub struct GetItemCache {
+    ttl: Duration,
+}
+
+impl GetItemCache {
+    /// Caches results for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl }
+    }
+
+    /// Purges the cached results for the item with `key` in `table`, in every POP, for example after updating it. The
+    /// key is given the way DynamoDB sends it over the wire:
+    ///
+    /// ```no_run
+    /// use aws_fastly_http_client::GetItemCache;
+    /// use serde_json::json;
+    ///
+    /// GetItemCache::invalidate("users", &json!({ "id": { "S": "user-1" } })).unwrap();
+    /// ```
+    pub fn invalidate(table: &str, key: &Value) -> Result<(), ItemCacheError> {
+        purge::purge_surrogate_key(&surrogate_key(table, key))
+            .map_err(|error| ItemCacheError::new(table, error))
+    }
+}
+
+/// The part of a `GetItem` request that decides its result.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetItem {
+    table_name: String,
+    key: Value,
+    #[serde(default)]
+    consistent_read: bool,
+    #[serde(flatten)]
+    rest: BTreeMap<String, Value>,
+}
+
+/// Where the result of a `GetItem` request is cached.
+struct CachedItem {
+    key: CacheKey,
+    surrogate_key: String,
+}
+
+impl CachedItem {
+    /// Identifies the result of `request`, if it's an eventually consistent `GetItem`.
+    fn for_request(request: &HttpRequest) -> Option<Self> {
+        if request.headers().get("x-amz-target") != Some(GET_ITEM) {
+            return None;
+        }
+
+        let get_item: GetItem = serde_json::from_slice(request.body().bytes()?).ok()?;
+
+        if get_item.consistent_read {
+            return None;
+        }
+
+        // Maps are serialized with sorted keys, so the same request always hashes the same.
+        let request = json!({
+            "endpoint": request.uri(),
+            "table": get_item.table_name,
+            "key": get_item.key,
+            "rest": get_item.rest,
+        });
+
+        Some(Self {
+            key: CacheKey::from(format!(
+                "aws-fastly-http-client/get-item/{}",
+                sha256(request.to_string().as_bytes())
+            )),
+            surrogate_key: surrogate_key(&get_item.table_name, &get_item.key),
+        })
+    }
+
+    /// The cached result, if there is one. Cache failures count as misses.
+    fn lookup(&self) -> Option<Vec<u8>> {
+        let found = core::lookup(self.key.clone()).execute().ok()??;
+
+        let mut body = Vec::new();
+        found.to_stream().ok()?.read_to_end(&mut body).ok()?;
+
+        Some(body)
+    }
+
+    /// Caches `body` for `ttl`. Cache failures are ignored, the result just gets read from DynamoDB again.
+    fn insert(&self, body: &[u8], ttl: Duration) {
+        let insert = core::insert(self.key.clone(), ttl)
+            .surrogate_keys([self.surrogate_key.as_str()])
+            .execute();
+
+        if let Ok(mut stream) = insert {
+            if stream.write_all(body).is_ok() {
+                let _ = stream.finish();
+            }
+        }
+    }
+}
+
+/// Serves `GetItem` results from the [GetItemCache] before they reach the wrapped connector.
+#[derive(Debug)]
+pub(crate) struct ItemCacheConnector {
+    pub(crate) connector: SharedHttpConnector,
+    pub(crate) cache: GetItemCache,
+}
+
+impl HttpConnector for ItemCacheConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let Some(item) = CachedItem::for_request(&request) else {
+            return self.connector.call(request);
+        };
+
+        if let Some(body) = item.lookup() {
+            return HttpConnectorFuture::ready(cached_response(body));
+        }
+
+        let response = self.connector.call(request);
+        let ttl = self.cache.ttl;
+
+        HttpConnectorFuture::new_boxed(Box::pin(async move {
+            let mut response = response.await?;
+
+            if response.status().as_u16() != 200 {
+                return Ok(response);
+            }
+
+            let body = mem::replace(response.body_mut(), SdkBody::taken());
+            let body = body::collect(body).await.map_err(ConnectorError::io)?;
+
+            item.insert(&body, ttl);
+            *response.body_mut() = SdkBody::from(body);
+
+            Ok(response)
+        }))
+    }
+}
+
+fn cached_response(body: Vec<u8>) -> Result<HttpResponse, ConnectorError> {
+    let response = http::Response::builder()
+        .status(200)
+        .header("content-type", "application/x-amz-json-1.0")
+        .body(SdkBody::from(body))
+        .map_err(ConversionError::response)?;
+
+    Ok(HttpResponse::try_from(response).map_err(ConversionError::response)?)
+}
+
+/// Tags every cached result for the item with `key` in `table`, whatever endpoint and projection it was read with.
+fn surrogate_key(table: &str, key: &Value) -> String {
+    format!(
+        "dynamodb/{table}/{}",
+        sha256(format!("{table}\n{key}").as_bytes())
+    )
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
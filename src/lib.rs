@@ -1,151 +1,259 @@
-use std::convert::TryFrom;
-use std::fmt::Debug;
-use std::future::Future;
-use std::pin::Pin;
-use std::task::{Context, Poll};
-use std::time::Duration;
+mod assume_role;
+mod backend;
+mod body;
+mod caching;
+mod circuit;
+mod classify;
+mod collapse;
+mod config;
+mod config_store;
+mod connector;
+mod credentials;
+mod error;
+mod executor;
+mod failover;
+mod geo;
+mod hedge;
+mod in_memory;
+mod item_cache;
+mod limit;
+mod reactor;
+mod recording;
+mod retry;
+mod routes;
+mod sleep;
+mod transport;
+
+use std::sync::Arc;
 
 use aws_smithy_runtime_api::client::http::{
-    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+    HttpClient, HttpConnectorSettings, SharedHttpConnector,
 };
-use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
-use aws_smithy_runtime_api::client::result::ConnectorError;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
-use aws_smithy_types::body::SdkBody;
 use fastly::convert::ToBackend;
-use fastly::http::request::{PendingRequest, PollResult, SendError, SendErrorCause};
-use fastly::{Backend, Body, Request, Response};
-use futures::TryFutureExt;
-use tokio::time::sleep;
+
+use crate::backend::{Backends, Target, Timeouts};
+use crate::circuit::Circuits;
+use crate::collapse::Collapser;
+use crate::connector::FastlyHttpConnector;
+use crate::item_cache::ItemCacheConnector;
+use crate::limit::{LimitedConnector, Limiter};
+use crate::reactor::Reactor;
+
+pub use crate::assume_role::FastlyAssumeRoleCredentialsProvider;
+pub use crate::caching::CachePolicy;
+pub use crate::circuit::CircuitBreaker;
+pub use crate::collapse::Collapsing;
+pub use crate::config::sdk_config;
+pub use crate::config_store::{FastlyConfigStoreLoader, FastlyConfigStoreRegionProvider};
+pub use crate::credentials::FastlySecretStoreCredentialsProvider;
+pub use crate::error::{
+    BackendError, CircuitOpenError, ConfigStoreError, ConversionError, ConversionErrorKind,
+    FastlyConnectorError, ItemCacheError, NoRouteError, SecretStoreError, SecretStoreErrorKind,
+    UnknownRegionError,
+};
+pub use crate::executor::block_on;
+pub use crate::failover::Failover;
+pub use crate::geo::{GeoRegions, RegionBackend};
+pub use crate::hedge::Hedging;
+pub use crate::in_memory::{InMemoryBody, InMemoryPending, InMemoryTransport, SentRequest};
+pub use crate::item_cache::GetItemCache;
+pub use crate::limit::ConcurrencyMetrics;
+pub use crate::recording::{Fixture, RecordingFastlyHttpClient, ReplayHttpClient};
+pub use crate::retry::FastlyRetryClassifier;
+pub use crate::routes::Routes;
+pub use crate::sleep::{FastlySleep, FastlyTimeSource};
+pub use crate::transport::{FastlyTransport, Transport, TransportError, TransportPoll};
 
 /// An HTTP client for communicating with AWS services. This is what you'll insert into your config.
 #[derive(Debug)]
-pub struct FastlyHttpClient {
-    backend: Backend,
+pub struct FastlyHttpClient<T: Transport = FastlyTransport> {
+    target: Target,
+    backends: Arc<Backends>,
+    transport: Arc<T>,
+    reactor: Arc<Reactor<T>>,
+    circuits: Arc<Circuits>,
+    limiter: Arc<Limiter>,
+    hedging: Option<Hedging>,
+    caching: Option<CachePolicy>,
+    item_cache: Option<GetItemCache>,
+    collapser: Arc<Collapser>,
 }
 
-impl<T: ToBackend> From<T> for FastlyHttpClient {
-    fn from(backend: T) -> Self {
+// Clones share backends, in-flight requests, circuits, collapsed requests and the concurrency limit, so metrics can be read from a clone
+// of a client that was handed to the SDK.
+impl<T: Transport> Clone for FastlyHttpClient<T> {
+    fn clone(&self) -> Self {
         Self {
-            backend: backend.into_owned(),
+            target: self.target.clone(),
+            backends: self.backends.clone(),
+            transport: self.transport.clone(),
+            reactor: self.reactor.clone(),
+            circuits: self.circuits.clone(),
+            limiter: self.limiter.clone(),
+            hedging: self.hedging.clone(),
+            caching: self.caching.clone(),
+            item_cache: self.item_cache.clone(),
+            collapser: self.collapser.clone(),
         }
     }
 }
 
-impl HttpClient for FastlyHttpClient {
-    fn http_connector(
-        &self,
-        _: &HttpConnectorSettings,
-        _: &RuntimeComponents,
-    ) -> SharedHttpConnector {
-        SharedHttpConnector::new(FastlyHttpConnector::from(self.backend.clone()))
+impl<B: ToBackend> From<B> for FastlyHttpClient {
+    fn from(backend: B) -> Self {
+        Self::new(Target::Backend(backend.into_owned()))
     }
 }
 
-#[derive(Debug)]
-struct FastlyHttpConnector {
-    backend: Backend,
-}
-
-impl From<Backend> for FastlyHttpConnector {
-    fn from(backend: Backend) -> Self {
-        Self { backend }
+impl FastlyHttpClient {
+    /// Creates a client that sends every request to a dynamic backend for the endpoint the SDK resolved for it, so a
+    /// single client can talk to any AWS service in any region without declaring backends up front. Backends are
+    /// created on first use and reused for the rest of the instance. This requires dynamic backends to be enabled for
+    /// your service.
+    pub fn dynamic() -> Self {
+        Self::new(Target::Endpoint)
     }
-}
 
-impl HttpConnector for FastlyHttpConnector {
-    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
-        let request = Request::from_http_request(request);
-
-        let future = match request.send_async(&self.backend) {
-            Ok(pending_request) => ResponseFuture::from(pending_request),
-            Err(error) => return HttpConnectorFuture::ready(Err(into_connector_error(error))),
-        };
-
-        let response = future
-            .map_ok(into_http_response)
-            .map_err(into_connector_error);
+    /// Creates a client that sends requests to the backend routed for their host. Requests for hosts without a route
+    /// fail with a [NoRouteError] rather than being sent to the wrong origin.
+    pub fn routed(routes: Routes) -> Self {
+        Self::new(Target::Routes(routes))
+    }
 
-        HttpConnectorFuture::new_boxed(Box::pin(response))
+    /// Creates a client that sends requests to the first healthy backend of `failover`, and on to the next one if that
+    /// backend can't be reached or fails with one of the failover statuses.
+    pub fn failover(failover: Failover) -> Self {
+        Self::new(Target::Failover(failover))
     }
-}
 
-trait FromHttpRequest {
-    fn from_http_request(request: HttpRequest) -> Self;
+    fn new(target: Target) -> Self {
+        Self {
+            target,
+            backends: Arc::default(),
+            transport: Arc::new(FastlyTransport),
+            reactor: Reactor::shared(),
+            circuits: Arc::default(),
+            limiter: Arc::default(),
+            hedging: None,
+            caching: None,
+            item_cache: None,
+            collapser: Arc::default(),
+        }
+    }
 }
 
-impl FromHttpRequest for Request {
-    fn from_http_request(request: HttpRequest) -> Self {
-        let to_fastly_body = |body: SdkBody| body.bytes().map(Body::from).unwrap_or(Body::new());
+impl<T: Transport> FastlyHttpClient<T> {
+    /// Sends requests through `transport` instead of the Fastly host, for example an [InMemoryTransport] in tests.
+    pub fn with_transport<U: Transport>(self, transport: U) -> FastlyHttpClient<U> {
+        FastlyHttpClient {
+            target: self.target,
+            backends: self.backends,
+            transport: Arc::new(transport),
+            reactor: Reactor::shared(),
+            circuits: self.circuits,
+            limiter: self.limiter,
+            hedging: self.hedging,
+            caching: self.caching,
+            item_cache: self.item_cache,
+            collapser: self.collapser,
+        }
+    }
 
-        request
-            .map(to_fastly_body)
-            .try_into_http02x()
-            .map(fastly::Request::from)
-            .unwrap()
+    /// Fails requests fast while their backend keeps failing, as configured by `breaker`.
+    pub fn with_circuit_breaker(self, breaker: CircuitBreaker) -> Self {
+        Self {
+            circuits: Arc::new(Circuits::new(breaker)),
+            ..self
+        }
     }
-}
 
-fn into_http_response(response: Response) -> HttpResponse {
-    let response: http::Response<Body> = response.into();
-    let to_sdk_body = |body: Body| SdkBody::from(body.into_bytes());
-    HttpResponse::try_from(response.map(to_sdk_body)).unwrap()
-}
+    /// Keeps at most `max_in_flight` requests in flight, queuing the rest until earlier ones get their response.
+    /// Compute limits how many requests can be pending at once and fails the ones over the limit with
+    /// `ConnectionLimitReached`, which is easy to run into when making lots of calls concurrently.
+    ///
+    /// # Panics
+    ///
+    /// If `max_in_flight` is 0, since no request could ever be sent.
+    pub fn with_max_in_flight(self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be at least 1");
 
-fn into_connector_error(error: SendError) -> ConnectorError {
-    match error.root_cause() {
-        SendErrorCause::BufferSize(_)
-        | SendErrorCause::DnsError { .. }
-        | SendErrorCause::ConnectionRefused
-        | SendErrorCause::ConnectionTerminated
-        | SendErrorCause::ConnectionLimitReached
-        | SendErrorCause::TlsProtocolError
-        | SendErrorCause::TlsAlertReceived { .. }
-        | SendErrorCause::TlsConfigurationError
-        | SendErrorCause::HttpIncompleteResponse
-        | SendErrorCause::HttpResponseHeaderSectionTooLarge
-        | SendErrorCause::HttpResponseBodyTooLarge
-        | SendErrorCause::HttpProtocolError => ConnectorError::io(Box::new(error)),
-        SendErrorCause::DnsTimeout
-        | SendErrorCause::ConnectionTimeout
-        | SendErrorCause::HttpResponseTimeout => ConnectorError::timeout(Box::new(error)),
-        _ => ConnectorError::other(Box::new(error), None),
+        Self {
+            limiter: Arc::new(Limiter::new(max_in_flight)),
+            ..self
+        }
     }
-}
 
-struct ResponseFuture {
-    pending_request: Option<PendingRequest>,
-}
+    /// Sends a duplicate of requests `hedging` allows when they're slow to get a response, and uses whichever response
+    /// comes first.
+    pub fn with_hedging(self, hedging: Hedging) -> Self {
+        Self {
+            hedging: Some(hedging),
+            ..self
+        }
+    }
 
-impl From<PendingRequest> for ResponseFuture {
-    fn from(pending_request: PendingRequest) -> Self {
+    /// Caches responses to requests `caching` allows at the POP, instead of leaving it to the default cache behavior.
+    pub fn with_caching(self, caching: CachePolicy) -> Self {
         Self {
-            pending_request: Some(pending_request),
+            caching: Some(caching),
+            ..self
         }
     }
-}
 
-impl Future for ResponseFuture {
-    type Output = Result<Response, SendError>;
+    /// Serves DynamoDB `GetItem` results from the Core Cache, as configured by `cache`.
+    pub fn with_item_cache(self, cache: GetItemCache) -> Self {
+        Self {
+            item_cache: Some(cache),
+            ..self
+        }
+    }
 
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let pending_request = self.pending_request.take().unwrap();
+    /// Sends identical requests `collapsing` allows only once while they're in flight, and hands the response to all of
+    /// them.
+    pub fn with_collapsing(self, collapsing: Collapsing) -> Self {
+        Self {
+            collapser: Arc::new(Collapser::new(collapsing)),
+            ..self
+        }
+    }
 
-        match pending_request.poll() {
-            PollResult::Done(result) => Poll::Ready(result),
-            PollResult::Pending(pending_request) => {
-                self.pending_request = Some(pending_request);
+    /// How many requests are in flight and queued right now, and how many were queued at most.
+    pub fn concurrency_metrics(&self) -> ConcurrencyMetrics {
+        self.limiter.metrics()
+    }
+}
 
-                let waker = cx.waker().clone();
-                let duration = Duration::from_millis(5);
+impl<T: Transport> HttpClient for FastlyHttpClient<T> {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        _: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        let connector = SharedHttpConnector::new(FastlyHttpConnector {
+            target: self.target.clone(),
+            timeouts: Timeouts::from(settings),
+            backends: self.backends.clone(),
+            transport: self.transport.clone(),
+            reactor: self.reactor.clone(),
+            circuits: self.circuits.clone(),
+            limiter: self.limiter.clone(),
+            hedging: self.hedging.clone(),
+            caching: self.caching.clone(),
+            collapser: self.collapser.clone(),
+        });
 
-                tokio::spawn(async move {
-                    sleep(duration).await;
-                    waker.wake();
-                });
+        let connector = SharedHttpConnector::new(LimitedConnector {
+            connector,
+            limiter: self.limiter.clone(),
+        });
 
-                Poll::Pending
-            }
+        // Cache hits don't take up a place in the concurrency limit.
+        match &self.item_cache {
+            Some(cache) => SharedHttpConnector::new(ItemCacheConnector {
+                connector,
+                cache: cache.clone(),
+            }),
+            None => connector,
         }
     }
 }
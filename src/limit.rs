@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use aws_smithy_runtime_api::client::http::{
+    HttpConnector, HttpConnectorFuture, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+
+/// A snapshot of how many requests a [FastlyHttpClient](crate::FastlyHttpClient) has in flight and queued, taken with
+/// [FastlyHttpClient::concurrency_metrics](crate::FastlyHttpClient::concurrency_metrics).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ConcurrencyMetrics {
+    in_flight: usize,
+    queued: usize,
+    peak_queued: usize,
+}
+
+impl ConcurrencyMetrics {
+    /// The number of requests that were sent and haven't got a response yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// The number of requests waiting for others to complete before they can be sent.
+    pub fn queued(&self) -> usize {
+        self.queued
+    }
+
+    /// The most requests that were queued at once.
+    pub fn peak_queued(&self) -> usize {
+        self.peak_queued
+    }
+}
+
+/// Caps the number of requests a client has in flight. Requests over the limit wait in line, in the order they were
+/// made, until earlier ones get their response.
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    max_in_flight: Option<usize>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    next_id: u64,
+    queue: VecDeque<(u64, Waker)>,
+    peak_queued: usize,
+}
+
+impl State {
+    fn wake_next(&self) {
+        if let Some((_, waker)) = self.queue.front() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+impl Limiter {
+    pub(crate) fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight: Some(max_in_flight),
+            state: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> ConcurrencyMetrics {
+        let state = self.state.lock().unwrap();
+
+        ConcurrencyMetrics {
+            in_flight: state.in_flight,
+            queued: state.queue.len(),
+            peak_queued: state.peak_queued,
+        }
+    }
+
+    fn has_capacity(&self, state: &State) -> bool {
+        match self.max_in_flight {
+            Some(max_in_flight) => state.in_flight < max_in_flight,
+            None => true,
+        }
+    }
+
+    /// Takes a permit if one is available and nobody is waiting for one.
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut state = self.state.lock().unwrap();
+
+        if !state.queue.is_empty() || !self.has_capacity(&state) {
+            return None;
+        }
+
+        state.in_flight += 1;
+        Some(Permit {
+            limiter: self.clone(),
+        })
+    }
+}
+
+/// Allows a request to be in flight. The next request in line is let through when it's dropped.
+#[derive(Debug)]
+pub(crate) struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        state.in_flight -= 1;
+        state.wake_next();
+    }
+}
+
+/// Waits in line for a [Permit].
+struct Acquire {
+    limiter: Arc<Limiter>,
+    id: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let limiter = self.limiter.clone();
+        let mut state = limiter.state.lock().unwrap();
+
+        let Some(id) = self.id else {
+            // Permits may have been freed since the request was turned away.
+            if state.queue.is_empty() && limiter.has_capacity(&state) {
+                state.in_flight += 1;
+                return Poll::Ready(Permit {
+                    limiter: limiter.clone(),
+                });
+            }
+
+            let id = state.next_id;
+            state.next_id += 1;
+            state.queue.push_back((id, cx.waker().clone()));
+            state.peak_queued = state.peak_queued.max(state.queue.len());
+            self.id = Some(id);
+            return Poll::Pending;
+        };
+
+        let is_next = state.queue.front().is_some_and(|(next, _)| *next == id);
+
+        if is_next && limiter.has_capacity(&state) {
+            state.queue.pop_front();
+            state.in_flight += 1;
+            self.id = None;
+
+            // There may be room for more than one.
+            state.wake_next();
+
+            return Poll::Ready(Permit {
+                limiter: limiter.clone(),
+            });
+        }
+
+        if let Some((_, waker)) = state.queue.iter_mut().find(|(queued, _)| *queued == id) {
+            waker.clone_from(cx.waker());
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+
+        let mut state = self.limiter.state.lock().unwrap();
+        state.queue.retain(|(queued, _)| *queued != id);
+
+        // Whoever is next in line might have been waiting on this one.
+        if self.limiter.has_capacity(&state) {
+            state.wake_next();
+        }
+    }
+}
+
+/// Holds a [Permit] from the [Limiter] for every request the wrapped connector sends, until it has got its response.
+#[derive(Debug)]
+pub(crate) struct LimitedConnector {
+    pub(crate) connector: SharedHttpConnector,
+    pub(crate) limiter: Arc<Limiter>,
+}
+
+impl HttpConnector for LimitedConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        if let Some(permit) = self.limiter.try_acquire() {
+            let response = self.connector.call(request);
+
+            return HttpConnectorFuture::new_boxed(Box::pin(async move {
+                let response = response.await;
+                drop(permit);
+                response
+            }));
+        }
+
+        let acquire = Acquire {
+            limiter: self.limiter.clone(),
+            id: None,
+        };
+        let connector = self.connector.clone();
+
+        HttpConnectorFuture::new_boxed(Box::pin(async move {
+            let permit = acquire.await;
+            let response = connector.call(request).await;
+            drop(permit);
+            response
+        }))
+    }
+}
@@ -0,0 +1,434 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+#[cfg(not(feature = "tokio"))]
+use std::{thread, time::Instant};
+
+use fastly::Response;
+
+#[cfg(not(feature = "tokio"))]
+use crate::sleep;
+use crate::transport::{Transport, TransportError, TransportPoll};
+
+/// How long to wait between polling requests while something else could be due first.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The reactors of every transport type that has sent requests, so they can all be waited on together.
+static REACTORS: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+/// Wakes the future driving the reactors when a request is registered, with the `tokio` feature enabled.
+#[cfg(feature = "tokio")]
+static DRIVER: Mutex<Option<Waker>> = Mutex::new(None);
+
+/// A reactor in [REACTORS], as itself so it can be handed to clients of its transport, and as a [Turn] so it can be
+/// waited on without knowing its transport.
+struct Registered {
+    reactor: Arc<dyn Any + Send + Sync>,
+    turn: Arc<dyn Turn>,
+}
+
+/// Identifies a pending request registered with a [Reactor].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct Token(u64);
+
+/// Keeps track of the outstanding requests of every client using a transport type, so that waiting for responses can
+/// be left to the transport instead of polling on a timer. Futures never block: a [ResponseFuture] that isn't done
+/// registers its request and returns. Once none of the futures of [block_on](crate::block_on) can make progress, it
+/// blocks in [Transport::select] until one of the requests completes and wakes the future waiting for it, unless a
+/// [FastlySleep](crate::FastlySleep) is due first.
+pub(crate) struct Reactor<T: Transport> {
+    inner: Mutex<Inner<T>>,
+}
+
+struct Inner<T: Transport> {
+    /// The transport to wait with, which may be given requests sent through any instance of it.
+    transport: Option<Arc<T>>,
+    next_token: u64,
+    pending: Vec<(Token, T::Pending)>,
+    done: HashMap<Token, Result<Response, TransportError>>,
+    wakers: HashMap<Token, Waker>,
+}
+
+impl<T: Transport> Default for Reactor<T> {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                transport: None,
+                next_token: 0,
+                pending: Vec::new(),
+                done: HashMap::new(),
+                wakers: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl<T: Transport> Reactor<T> {
+    /// The reactor shared by every client using `T`.
+    pub(crate) fn shared() -> Arc<Self> {
+        let mut reactors = REACTORS.lock().unwrap();
+
+        for registered in reactors.iter() {
+            if let Ok(reactor) = registered.reactor.clone().downcast::<Self>() {
+                return reactor;
+            }
+        }
+
+        let reactor = Arc::new(Self::default());
+        reactors.push(Registered {
+            reactor: reactor.clone(),
+            turn: reactor.clone(),
+        });
+
+        reactor
+    }
+
+    /// Starts tracking a request that is still in flight, returning the token to poll it with.
+    fn register(&self, transport: &Arc<T>, pending: T::Pending, waker: &Waker) -> Token {
+        let token = {
+            let mut inner = self.inner.lock().unwrap();
+
+            let token = Token(inner.next_token);
+            inner.next_token += 1;
+            inner.transport.get_or_insert_with(|| transport.clone());
+            inner.pending.push((token, pending));
+            inner.wakers.insert(token, waker.clone());
+
+            token
+        };
+
+        #[cfg(feature = "tokio")]
+        if let Some(driver) = DRIVER.lock().unwrap().take() {
+            driver.wake();
+        }
+
+        token
+    }
+
+    /// Takes the result of the request behind the token if it completed, and otherwise leaves the waker to wake once it
+    /// does.
+    fn take(&self, token: Token, waker: &Waker) -> Option<Result<Response, TransportError>> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.done.remove(&token) {
+            Some(result) => {
+                inner.wakers.remove(&token);
+                Some(result)
+            }
+            None => {
+                inner.wakers.insert(token, waker.clone());
+                None
+            }
+        }
+    }
+
+    /// Stops tracking a request, abandoning it if it is still in flight.
+    fn cancel(&self, token: Token) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner
+            .pending
+            .retain(|(pending_token, _)| *pending_token != token);
+        inner.done.remove(&token);
+        inner.wakers.remove(&token);
+    }
+
+    /// Takes the outstanding requests, along with the transport to wait on them with.
+    fn take_pending(&self) -> Option<(Arc<T>, Vec<(Token, T::Pending)>)> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.pending.is_empty() {
+            return None;
+        }
+
+        let transport = inner.transport.clone()?;
+        Some((transport, mem::take(&mut inner.pending)))
+    }
+
+    /// Puts back the requests that are still outstanding, and wakes the futures waiting for the ones that completed.
+    fn complete(&self, completed: Vec<Completed>, remaining: Vec<(Token, T::Pending)>) {
+        let wakers: Vec<_> = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.pending.extend(remaining);
+
+            completed
+                .into_iter()
+                .filter_map(|(token, result)| {
+                    inner.done.insert(token, result);
+                    inner.wakers.remove(&token)
+                })
+                .collect()
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// What the idle step needs from a [Reactor], whatever its transport.
+trait Turn: Send + Sync {
+    /// Whether any requests are outstanding.
+    fn is_waiting(&self) -> bool;
+
+    /// Polls every outstanding request once without blocking. Returns whether any of them completed.
+    fn poll_once(&self) -> bool;
+
+    /// Blocks until one of the outstanding requests completes, or until the deadline passes if there is one.
+    #[cfg(not(feature = "tokio"))]
+    fn wait(&self, deadline: Option<Instant>);
+}
+
+impl<T: Transport> Turn for Reactor<T> {
+    fn is_waiting(&self) -> bool {
+        !self.inner.lock().unwrap().pending.is_empty()
+    }
+
+    fn poll_once(&self) -> bool {
+        let Some((transport, pending)) = self.take_pending() else {
+            return false;
+        };
+
+        let (completed, remaining) = poll_all(transport.as_ref(), pending);
+        let any_completed = !completed.is_empty();
+        self.complete(completed, remaining);
+
+        any_completed
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn wait(&self, deadline: Option<Instant>) {
+        let Some((transport, pending)) = self.take_pending() else {
+            return;
+        };
+
+        let (completed, remaining) = match deadline {
+            Some(deadline) => poll_until(transport.as_ref(), pending, deadline),
+            None => {
+                let (completed, remaining) = wait(transport.as_ref(), pending);
+                (vec![completed], remaining)
+            }
+        };
+
+        self.complete(completed, remaining);
+    }
+}
+
+/// Waits for whatever the futures of [block_on](crate::block_on) are waiting on, once none of them can make progress:
+/// blocks until an outstanding request completes or the next sleep is due, and wakes whatever was waiting for it.
+/// Requests of a single transport type are waited on by the host, but if several types have requests outstanding,
+/// they're polled in turn instead. Returns false if nothing is in flight or sleeping.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn idle() -> bool {
+    let waiting = waiting();
+    let deadline = sleep::next_deadline();
+
+    match waiting.as_slice() {
+        [] => return sleep::park(),
+        [reactor] => reactor.wait(deadline),
+        reactors => poll_reactors_until(reactors, deadline),
+    }
+
+    sleep::fire();
+    true
+}
+
+/// Drives the reactors alongside the future of [block_on](crate::block_on) with the `tokio` feature enabled. Tokio's
+/// timers can't be waited on together with requests, so requests are polled once a millisecond while any are
+/// outstanding, and the driver sleeps until one is registered otherwise.
+#[cfg(feature = "tokio")]
+pub(crate) async fn drive() -> std::convert::Infallible {
+    loop {
+        std::future::poll_fn(|cx| {
+            *DRIVER.lock().unwrap() = Some(cx.waker().clone());
+
+            let waiting = waiting();
+            if waiting.is_empty() {
+                return Poll::Pending;
+            }
+
+            for reactor in waiting {
+                reactor.poll_once();
+            }
+
+            DRIVER.lock().unwrap().take();
+            Poll::Ready(())
+        })
+        .await;
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// The reactors with requests outstanding.
+fn waiting() -> Vec<Arc<dyn Turn>> {
+    REACTORS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|registered| registered.turn.clone())
+        .filter(|turn| turn.is_waiting())
+        .collect()
+}
+
+/// Polls the requests of several reactors until one of them completes or the deadline passes.
+#[cfg(not(feature = "tokio"))]
+fn poll_reactors_until(reactors: &[Arc<dyn Turn>], deadline: Option<Instant>) {
+    loop {
+        let completed = reactors
+            .iter()
+            .fold(false, |completed, reactor| reactor.poll_once() || completed);
+
+        let now = Instant::now();
+
+        if completed || deadline.is_some_and(|deadline| now >= deadline) {
+            return;
+        }
+
+        let interval = match deadline {
+            Some(deadline) => POLL_INTERVAL.min(deadline - now),
+            None => POLL_INTERVAL,
+        };
+
+        thread::sleep(interval);
+    }
+}
+
+/// A request that completed, along with the token it was registered with.
+type Completed = (Token, Result<Response, TransportError>);
+
+/// Blocks until one of the requests completes.
+#[cfg(not(feature = "tokio"))]
+fn wait<T: Transport>(
+    transport: &T,
+    pending: Vec<(Token, T::Pending)>,
+) -> (Completed, Vec<(Token, T::Pending)>) {
+    if pending.len() == 1 {
+        let (token, pending) = pending.into_iter().next().unwrap();
+        return ((token, transport.wait(pending)), Vec::new());
+    }
+
+    let (mut tokens, pending): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+    let (index, result, pending) = transport.select(pending);
+    let token = tokens.remove(index);
+
+    ((token, result), tokens.into_iter().zip(pending).collect())
+}
+
+/// Polls each of the requests once.
+fn poll_all<T: Transport>(
+    transport: &T,
+    pending: Vec<(Token, T::Pending)>,
+) -> (Vec<Completed>, Vec<(Token, T::Pending)>) {
+    let mut completed = Vec::new();
+    let mut remaining = Vec::with_capacity(pending.len());
+
+    for (token, request) in pending {
+        match transport.poll(request) {
+            TransportPoll::Done(result) => completed.push((token, result)),
+            TransportPoll::Pending(request) => remaining.push((token, request)),
+        }
+    }
+
+    (completed, remaining)
+}
+
+/// Polls the requests until one of them completes or the deadline passes, whichever comes first.
+#[cfg(not(feature = "tokio"))]
+fn poll_until<T: Transport>(
+    transport: &T,
+    mut pending: Vec<(Token, T::Pending)>,
+    deadline: Instant,
+) -> (Vec<Completed>, Vec<(Token, T::Pending)>) {
+    loop {
+        let (completed, remaining) = poll_all(transport, pending);
+        let now = Instant::now();
+
+        if !completed.is_empty() || now >= deadline {
+            return (completed, remaining);
+        }
+
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+        pending = remaining;
+    }
+}
+
+impl<T: Transport> Debug for Reactor<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+
+        f.debug_struct("Reactor")
+            .field("pending", &inner.pending.len())
+            .field("done", &inner.done.len())
+            .finish()
+    }
+}
+
+/// Resolves once the backend responds. Requests that aren't done on the first poll are handed to the [Reactor],
+/// which wakes the future when the transport reports the response.
+pub(crate) struct ResponseFuture<T: Transport> {
+    transport: Arc<T>,
+    reactor: Arc<Reactor<T>>,
+    state: ResponseState<T::Pending>,
+}
+
+enum ResponseState<P> {
+    Sent(P),
+    Registered(Token),
+    Done,
+}
+
+impl<T: Transport> ResponseFuture<T> {
+    pub(crate) fn new(pending: T::Pending, transport: Arc<T>, reactor: Arc<Reactor<T>>) -> Self {
+        Self {
+            transport,
+            reactor,
+            state: ResponseState::Sent(pending),
+        }
+    }
+}
+
+// The pending request is never pinned.
+impl<T: Transport> Unpin for ResponseFuture<T> {}
+
+impl<T: Transport> Future for ResponseFuture<T> {
+    type Output = Result<Response, TransportError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let token = match mem::replace(&mut self.state, ResponseState::Done) {
+            ResponseState::Sent(pending) => match self.transport.poll(pending) {
+                TransportPoll::Done(result) => return Poll::Ready(result),
+                TransportPoll::Pending(pending) => {
+                    let token = self.reactor.register(&self.transport, pending, cx.waker());
+                    self.state = ResponseState::Registered(token);
+                    return Poll::Pending;
+                }
+            },
+            ResponseState::Registered(token) => token,
+            ResponseState::Done => panic!("ResponseFuture polled after completion"),
+        };
+
+        match self.reactor.take(token, cx.waker()) {
+            Some(result) => Poll::Ready(result),
+            None => {
+                self.state = ResponseState::Registered(token);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: Transport> Drop for ResponseFuture<T> {
+    fn drop(&mut self) {
+        if let ResponseState::Registered(token) = self.state {
+            self.reactor.cancel(token);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::body::SdkBody;
+use serde::{Deserialize, Serialize};
+
+use crate::body;
+use crate::classify::VOLATILE_HEADERS;
+use crate::error::ConversionError;
+use crate::transport::{FastlyTransport, Transport};
+use crate::FastlyHttpClient;
+
+/// Requests and the responses they got, recorded by a [RecordingFastlyHttpClient] and served by a [ReplayHttpClient].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Fixture {
+    interactions: Vec<Interaction>,
+}
+
+impl Fixture {
+    /// Reads a fixture written by [Fixture::to_json].
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Writes the fixture as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("fixtures are always serializable")
+    }
+
+    /// The number of recorded requests.
+    pub fn len(&self) -> usize {
+        self.interactions.len()
+    }
+
+    /// Whether no requests were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.interactions.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct RecordedRequest {
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    /// Streaming request bodies aren't recorded.
+    body: Option<RecordedBody>,
+}
+
+impl From<&HttpRequest> for RecordedRequest {
+    fn from(request: &HttpRequest) -> Self {
+        Self {
+            method: request.method().to_string(),
+            uri: request.uri().to_string(),
+            headers: headers(request.headers().iter()),
+            body: request.body().bytes().map(RecordedBody::from),
+        }
+    }
+}
+
+impl RecordedRequest {
+    /// The request without the given headers, with header names lowercased and sorted, for comparing requests.
+    fn normalized(&self, ignored_headers: &[String]) -> Self {
+        let mut headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.clone()))
+            .filter(|(name, _)| !ignored_headers.contains(name))
+            .collect();
+
+        headers.sort();
+
+        Self {
+            headers,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: RecordedBody,
+}
+
+impl RecordedResponse {
+    fn into_http_response(self) -> Result<HttpResponse, ConnectorError> {
+        let mut response = http::Response::builder().status(self.status);
+
+        for (name, value) in self.headers {
+            response = response.header(name, value);
+        }
+
+        let response = response
+            .body(SdkBody::from(Vec::from(self.body)))
+            .map_err(ConversionError::response)?;
+
+        Ok(HttpResponse::try_from(response).map_err(ConversionError::response)?)
+    }
+}
+
+/// Bodies are kept as text where possible, so fixtures can be read and edited by hand.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+enum RecordedBody {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<&[u8]> for RecordedBody {
+    fn from(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_string()),
+            Err(_) => Self::Binary(bytes.to_vec()),
+        }
+    }
+}
+
+impl From<RecordedBody> for Vec<u8> {
+    fn from(body: RecordedBody) -> Self {
+        match body {
+            RecordedBody::Text(text) => text.into_bytes(),
+            RecordedBody::Binary(bytes) => bytes,
+        }
+    }
+}
+
+fn headers<'a>(headers: impl Iterator<Item = (&'a str, &'a str)>) -> Vec<(String, String)> {
+    headers
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Wraps a [FastlyHttpClient] and records every request it sends along with the response it got, so they can be
+/// replayed with a [ReplayHttpClient] later. Response bodies are read into memory to record them, so this is meant for
+/// capturing fixtures, e.g. in Viceroy, rather than for production:
+///
+/// ```no_run
+/// use aws_fastly_http_client::{FastlyHttpClient, RecordingFastlyHttpClient};
+///
+/// let http_client = RecordingFastlyHttpClient::new(FastlyHttpClient::from("dynamodb"));
+/// // Make some calls to AWS with `http_client.clone()`.
+///
+/// println!("{}", http_client.fixture().to_json());
+/// ```
+#[derive(Debug)]
+pub struct RecordingFastlyHttpClient<T: Transport = FastlyTransport> {
+    client: Arc<FastlyHttpClient<T>>,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl<T: Transport> Clone for RecordingFastlyHttpClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            fixture: self.fixture.clone(),
+        }
+    }
+}
+
+impl<T: Transport> RecordingFastlyHttpClient<T> {
+    /// Records the requests sent by `client`.
+    pub fn new(client: FastlyHttpClient<T>) -> Self {
+        Self {
+            client: Arc::new(client),
+            fixture: Arc::default(),
+        }
+    }
+
+    /// Everything recorded so far.
+    pub fn fixture(&self) -> Fixture {
+        self.fixture.lock().unwrap().clone()
+    }
+}
+
+impl<T: Transport> HttpClient for RecordingFastlyHttpClient<T> {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RecordingConnector {
+            connector: self.client.http_connector(settings, components),
+            fixture: self.fixture.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RecordingConnector {
+    connector: SharedHttpConnector,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let recorded_request = RecordedRequest::from(&request);
+        let response = self.connector.call(request);
+        let fixture = self.fixture.clone();
+
+        HttpConnectorFuture::new_boxed(Box::pin(async move {
+            let mut response = response.await?;
+
+            let body = mem::replace(response.body_mut(), SdkBody::taken());
+            let body = body::collect(body).await.map_err(ConnectorError::io)?;
+
+            let recorded_response = RecordedResponse {
+                status: response.status().as_u16(),
+                headers: headers(response.headers().iter()),
+                body: RecordedBody::from(body.as_slice()),
+            };
+
+            *response.body_mut() = SdkBody::from(body);
+
+            fixture.lock().unwrap().interactions.push(Interaction {
+                request: recorded_request,
+                response: recorded_response,
+            });
+
+            Ok(response)
+        }))
+    }
+}
+
+/// Serves the responses in a [Fixture] in the order they were recorded, without sending anything anywhere, so SDK code
+/// can run deterministically in CI. Every request is checked against the recorded one, ignoring headers that change
+/// whenever a request is signed, and a mismatch panics.
+#[derive(Clone, Debug)]
+pub struct ReplayHttpClient {
+    interactions: Arc<Mutex<VecDeque<Interaction>>>,
+    ignored_headers: Arc<Vec<String>>,
+}
+
+impl ReplayHttpClient {
+    /// Replays the interactions in `fixture`.
+    pub fn new(fixture: Fixture) -> Self {
+        Self {
+            interactions: Arc::new(Mutex::new(fixture.interactions.into())),
+            ignored_headers: Arc::new(
+                VOLATILE_HEADERS
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Also ignores `header` when checking requests.
+    pub fn ignore_header(mut self, header: &str) -> Self {
+        Arc::make_mut(&mut self.ignored_headers).push(header.to_ascii_lowercase());
+        self
+    }
+
+    /// The number of recorded interactions that haven't been replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.interactions.lock().unwrap().len()
+    }
+}
+
+impl HttpClient for ReplayHttpClient {
+    fn http_connector(
+        &self,
+        _: &HttpConnectorSettings,
+        _: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(self.clone())
+    }
+}
+
+impl HttpConnector for ReplayHttpClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let Some(interaction) = self.interactions.lock().unwrap().pop_front() else {
+            panic!(
+                "no recorded response left for {} {}",
+                request.method(),
+                request.uri()
+            );
+        };
+
+        let mut expected = interaction.request.normalized(&self.ignored_headers);
+        let mut actual = RecordedRequest::from(&request).normalized(&self.ignored_headers);
+
+        // Streaming bodies aren't recorded, so there's nothing to compare them with.
+        if expected.body.is_none() || actual.body.is_none() {
+            expected.body = None;
+            actual.body = None;
+        }
+
+        assert_eq!(expected, actual, "request doesn't match the recorded one");
+
+        HttpConnectorFuture::ready(interaction.response.into_http_response())
+    }
+}
@@ -0,0 +1,145 @@
+use std::error::Error;
+
+use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
+use aws_smithy_runtime_api::client::retries::classifiers::{
+    ClassifyRetry, RetryAction, RetryClassifierPriority,
+};
+use fastly::http::request::SendErrorCause;
+
+use crate::error::FastlyConnectorError;
+
+/// Methods that can be retried after a response was cut off without risking doing something twice.
+const IDEMPOTENT_METHODS: &[&str] = &["GET", "HEAD", "OPTIONS", "PUT", "DELETE"];
+
+/// Tells the SDK which Fastly send failures are worth retrying, so standard retries can be enabled on Compute:
+///
+/// ```no_run
+/// # use aws_smithy_types::retry::RetryConfig;
+/// use aws_fastly_http_client::{FastlyHttpClient, FastlyRetryClassifier};
+///
+/// let config = aws_sdk_dynamodb::Config::builder()
+///     .http_client(FastlyHttpClient::from("dynamodb"))
+///     .retry_config(RetryConfig::standard())
+///     .retry_classifier(FastlyRetryClassifier)
+///     .build();
+/// ```
+///
+/// Failures to connect, connection limits and DNS timeouts are retried. Incomplete responses are only retried for
+/// idempotent methods, and TLS configuration errors are never retried, since they'll fail the same way every time. It
+/// runs after the SDK's own classifiers and overrides them for these failures.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FastlyRetryClassifier;
+
+impl ClassifyRetry for FastlyRetryClassifier {
+    fn classify_retry(&self, ctx: &InterceptorContext) -> RetryAction {
+        let Some(Err(error)) = ctx.output_or_error() else {
+            return RetryAction::NoActionIndicated;
+        };
+
+        let Some(error) = error
+            .as_connector_error()
+            .and_then(|error| error.source())
+            .and_then(|source| source.downcast_ref::<FastlyConnectorError>())
+        else {
+            return RetryAction::NoActionIndicated;
+        };
+
+        match error.cause() {
+            SendErrorCause::ConnectionLimitReached
+            | SendErrorCause::ConnectionRefused
+            | SendErrorCause::DnsTimeout => RetryAction::transient_error(),
+            SendErrorCause::HttpIncompleteResponse if is_idempotent(error.method()) => {
+                RetryAction::transient_error()
+            }
+            SendErrorCause::HttpIncompleteResponse | SendErrorCause::TlsConfigurationError => {
+                RetryAction::RetryForbidden
+            }
+            _ => RetryAction::NoActionIndicated,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Fastly send errors"
+    }
+
+    fn priority(&self) -> RetryClassifierPriority {
+        RetryClassifierPriority::run_after(RetryClassifierPriority::transient_error_classifier())
+    }
+}
+
+// The request has been taken out of the context to be sent by the time this runs, so the method comes from the error.
+fn is_idempotent(method: &str) -> bool {
+    IDEMPOTENT_METHODS.contains(&method)
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_smithy_runtime_api::client::interceptors::context::{Error, Input};
+    use aws_smithy_runtime_api::client::orchestrator::OrchestratorError;
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+
+    use super::*;
+    use crate::transport::TransportError;
+
+    fn classify(method: &str, cause: SendErrorCause) -> RetryAction {
+        let error = TransportError::new("dynamodb", cause);
+        let error = FastlyConnectorError::new(method, "https://dynamodb/", error);
+
+        let mut ctx = InterceptorContext::new(Input::doesnt_matter());
+        ctx.set_output_or_error(Err(OrchestratorError::connector(ConnectorError::from(
+            error,
+        ))));
+
+        FastlyRetryClassifier.classify_retry(&ctx)
+    }
+
+    #[test]
+    fn retries_failures_to_connect() {
+        for cause in [
+            SendErrorCause::ConnectionRefused,
+            SendErrorCause::ConnectionLimitReached,
+            SendErrorCause::DnsTimeout,
+        ] {
+            assert_eq!(classify("POST", cause), RetryAction::transient_error());
+        }
+    }
+
+    #[test]
+    fn only_retries_incomplete_responses_to_idempotent_methods() {
+        for method in ["GET", "HEAD", "PUT", "DELETE"] {
+            assert_eq!(
+                classify(method, SendErrorCause::HttpIncompleteResponse),
+                RetryAction::transient_error()
+            );
+        }
+
+        assert_eq!(
+            classify("POST", SendErrorCause::HttpIncompleteResponse),
+            RetryAction::RetryForbidden
+        );
+    }
+
+    #[test]
+    fn never_retries_tls_configuration_errors() {
+        assert_eq!(
+            classify("GET", SendErrorCause::TlsConfigurationError),
+            RetryAction::RetryForbidden
+        );
+    }
+
+    #[test]
+    fn leaves_other_errors_to_the_sdk() {
+        assert_eq!(
+            classify("GET", SendErrorCause::HttpProtocolError),
+            RetryAction::NoActionIndicated
+        );
+
+        let mut ctx = InterceptorContext::new(Input::doesnt_matter());
+        ctx.set_output_or_error(Err(OrchestratorError::operation(Error::doesnt_matter())));
+
+        assert_eq!(
+            FastlyRetryClassifier.classify_retry(&ctx),
+            RetryAction::NoActionIndicated
+        );
+    }
+}
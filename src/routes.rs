@@ -0,0 +1,135 @@
+use fastly::convert::ToBackend;
+use fastly::Backend;
+
+/// A routing table mapping request hosts to static backends, for services that can't use dynamic backends. Hosts are
+/// matched case-insensitively against each route in the order they were added, and may contain `*` wildcards:
+///
+/// ```no_run
+/// use aws_fastly_http_client::{FastlyHttpClient, Routes};
+///
+/// let routes = Routes::new()
+///     .route("dynamodb.us-east-1.amazonaws.com", "dynamodb_us_east_1")
+///     .route("*.s3.eu-west-1.amazonaws.com", "s3_eu_west_1");
+///
+/// let http_client = FastlyHttpClient::routed(routes);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Routes {
+    routes: Vec<(String, Backend)>,
+}
+
+impl Routes {
+    /// Creates an empty routing table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends requests for hosts matching `pattern` to `backend`.
+    pub fn route(mut self, pattern: impl Into<String>, backend: impl ToBackend) -> Self {
+        let pattern = pattern.into().to_ascii_lowercase();
+        self.routes.push((pattern, backend.into_owned()));
+        self
+    }
+
+    /// Returns the backend for the first route matching `host`.
+    pub(crate) fn backend_for(&self, host: &str) -> Option<&Backend> {
+        let host = host.to_ascii_lowercase();
+
+        self.routes
+            .iter()
+            .find(|(pattern, _)| matches(pattern, &host))
+            .map(|(_, backend)| backend)
+    }
+}
+
+/// Matches a host against a pattern where `*` stands for any number of characters.
+fn matches(pattern: &str, host: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == host;
+    };
+
+    let Some(host) = host.strip_prefix(prefix) else {
+        return false;
+    };
+
+    // Try every position for the wildcard, since the rest of the pattern may contain more of them.
+    (0..=host.len())
+        .filter(|&index| host.is_char_boundary(index))
+        .any(|index| matches(rest, &host[index..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use fastly::Request;
+
+    use super::*;
+    use crate::backend::{Backends, Target, Timeouts};
+    use crate::error::NoRouteError;
+    use crate::in_memory::InMemoryTransport;
+
+    #[test]
+    fn matches_exact_hosts() {
+        assert!(matches("s3.amazonaws.com", "s3.amazonaws.com"));
+        assert!(!matches("s3.amazonaws.com", "s3.amazonaws.com.evil"));
+        assert!(!matches("s3.amazonaws.com", "bucket.s3.amazonaws.com"));
+    }
+
+    #[test]
+    fn matches_a_leading_wildcard() {
+        assert!(matches("*.s3.amazonaws.com", "bucket.s3.amazonaws.com"));
+        assert!(matches("*.s3.amazonaws.com", "a.b.s3.amazonaws.com"));
+        assert!(!matches("*.s3.amazonaws.com", "s3.amazonaws.com"));
+    }
+
+    #[test]
+    fn matches_several_wildcards() {
+        assert!(matches(
+            "*.s3.*.amazonaws.com",
+            "bucket.s3.eu-west-1.amazonaws.com"
+        ));
+        assert!(matches("dynamodb.*.*", "dynamodb.us-east-1.amazonaws.com"));
+        assert!(matches("*a*a*", "banana"));
+        assert!(!matches(
+            "*.s3.*.amazonaws.com",
+            "bucket.sqs.eu-west-1.amazonaws.com"
+        ));
+        assert!(!matches("*a*a*a*a*", "banana"));
+    }
+
+    #[test]
+    fn routes_hosts_case_insensitively_in_order() {
+        let routes = Routes::new()
+            .route("DynamoDB.us-east-1.amazonaws.com", "dynamodb")
+            .route("*.amazonaws.com", "aws");
+
+        let backend_for = |host| routes.backend_for(host).map(Backend::name);
+
+        assert_eq!(
+            backend_for("dynamodb.US-EAST-1.amazonaws.com"),
+            Some("dynamodb")
+        );
+        assert_eq!(backend_for("sts.amazonaws.com"), Some("aws"));
+        assert_eq!(backend_for("example.com"), None);
+    }
+
+    #[test]
+    fn fails_requests_for_hosts_without_a_route() {
+        let target = Target::Routes(Routes::new().route("*.amazonaws.com", "aws"));
+        let request = Request::get("https://example.com/");
+
+        let error = Backends::default()
+            .resolve(
+                &InMemoryTransport::new(),
+                &target,
+                &request,
+                Timeouts::default(),
+            )
+            .unwrap_err();
+
+        let error = ConnectorError::into_source(error)
+            .downcast::<NoRouteError>()
+            .unwrap();
+        assert_eq!(error.host(), "example.com");
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, SystemTime};
+
+#[cfg(not(feature = "tokio"))]
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+    time::Instant,
+};
+
+use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep};
+use aws_smithy_async::time::TimeSource;
+
+/// Sleeps waiting for their deadline, keyed by deadline and a unique id so the one due first comes first. They're
+/// added as soon as they're created, so requests polled before them don't block past their deadline, and get a waker
+/// once they're polled.
+#[cfg(not(feature = "tokio"))]
+static TIMERS: Mutex<BTreeMap<(Instant, u64), Option<Waker>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(not(feature = "tokio"))]
+static NEXT_TIMER: AtomicU64 = AtomicU64::new(0);
+
+/// An [AsyncSleep] for Compute, which the SDK needs for retry backoff, timeouts and stalled stream protection:
+///
+/// ```no_run
+/// # use aws_smithy_types::retry::RetryConfig;
+/// use aws_fastly_http_client::{FastlyHttpClient, FastlySleep, FastlyTimeSource};
+///
+/// let config = aws_sdk_dynamodb::Config::builder()
+///     .http_client(FastlyHttpClient::from("dynamodb"))
+///     .sleep_impl(FastlySleep)
+///     .time_source(FastlyTimeSource)
+///     .retry_config(RetryConfig::standard())
+///     .build();
+/// ```
+///
+/// Sleeps don't need a runtime: [block_on](crate::block_on) parks until the next one is due, and while requests are in
+/// flight the client polls them until then instead of blocking on the backends. With the `tokio` feature enabled,
+/// sleeps are left to Tokio's timer instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FastlySleep;
+
+impl AsyncSleep for FastlySleep {
+    #[cfg(not(feature = "tokio"))]
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep::new(Timer::new(duration))
+    }
+
+    #[cfg(feature = "tokio")]
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep::new(tokio::time::sleep(duration))
+    }
+}
+
+/// A [TimeSource] reading the clock of the Compute instance, used by the SDK for signing and timeouts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FastlyTimeSource;
+
+impl TimeSource for FastlyTimeSource {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Resolves once its deadline passes. Until then, it's kept in [TIMERS] so it can be woken on time.
+#[cfg(not(feature = "tokio"))]
+struct Timer {
+    deadline: Instant,
+    id: u64,
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Timer {
+    fn new(duration: Duration) -> Self {
+        let timer = Self {
+            deadline: Instant::now() + duration,
+            id: NEXT_TIMER.fetch_add(1, Ordering::Relaxed),
+        };
+
+        TIMERS
+            .lock()
+            .unwrap()
+            .insert((timer.deadline, timer.id), None);
+
+        timer
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut timers = TIMERS.lock().unwrap();
+
+        if Instant::now() >= self.deadline {
+            timers.remove(&(self.deadline, self.id));
+            return Poll::Ready(());
+        }
+
+        timers.insert((self.deadline, self.id), Some(cx.waker().clone()));
+        Poll::Pending
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        TIMERS.lock().unwrap().remove(&(self.deadline, self.id));
+    }
+}
+
+/// The deadline of the sleep that's due first, if anything is sleeping.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn next_deadline() -> Option<Instant> {
+    TIMERS
+        .lock()
+        .unwrap()
+        .keys()
+        .next()
+        .map(|(deadline, _)| *deadline)
+}
+
+/// Wakes every sleep whose deadline has passed.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn fire() {
+    let now = Instant::now();
+
+    let expired = {
+        let mut timers = TIMERS.lock().unwrap();
+        let sleeping = timers.split_off(&(now, u64::MAX));
+        mem::replace(&mut *timers, sleeping)
+    };
+
+    for waker in expired.into_values().flatten() {
+        waker.wake();
+    }
+}
+
+/// Blocks until the next sleep is due and wakes it. Returns false if nothing is sleeping.
+#[cfg(not(feature = "tokio"))]
+pub(crate) fn park() -> bool {
+    let Some(deadline) = next_deadline() else {
+        return false;
+    };
+
+    if let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        std::thread::sleep(remaining);
+    }
+
+    fire();
+    true
+}
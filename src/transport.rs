@@ -0,0 +1,208 @@
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use fastly::backend::{BackendBuilder, BackendCreationError};
+use fastly::http::body::StreamingBody;
+use fastly::http::request::{self, PendingRequest, PollResult, SendError, SendErrorCause};
+use fastly::{Backend, Request, Response};
+
+/// How a client exchanges requests and responses with backends. [FastlyTransport] sends them through the Fastly host
+/// and is what you'll use in production, while [InMemoryTransport](crate::InMemoryTransport) serves scripted
+/// responses so code using the client can be tested without one.
+///
+/// The requests of every client using the same transport type are waited on together, so [Transport::poll],
+/// [Transport::wait] and [Transport::select] may be given requests that were sent through another instance of it.
+pub trait Transport: Debug + Send + Sync + 'static {
+    /// A request that has been sent, but hasn't completed yet.
+    type Pending: Send + 'static;
+
+    /// The body of a request that is still being streamed to the backend.
+    type StreamingBody: Write + Send + 'static;
+
+    /// Sends a request to a backend without waiting for it to complete.
+    fn send(&self, request: Request, backend: &Backend) -> Result<Self::Pending, TransportError>;
+
+    /// Sends a request to a backend, returning a body to stream the rest of the request body into.
+    fn send_streaming(
+        &self,
+        request: Request,
+        backend: &Backend,
+    ) -> Result<(Self::StreamingBody, Self::Pending), TransportError>;
+
+    /// Finishes streaming a request body.
+    fn finish(&self, body: Self::StreamingBody) -> Result<(), BoxError>;
+
+    /// Checks whether a request has completed, without blocking.
+    fn poll(&self, pending: Self::Pending) -> TransportPoll<Self::Pending>;
+
+    /// Blocks until a request completes.
+    fn wait(&self, pending: Self::Pending) -> Result<Response, TransportError>;
+
+    /// Blocks until the first of several requests completes, returning its index along with the requests that are
+    /// still pending, in their original order.
+    fn select(
+        &self,
+        pending: Vec<Self::Pending>,
+    ) -> (usize, Result<Response, TransportError>, Vec<Self::Pending>);
+
+    /// Registers a dynamic backend named `name`, set up by `builder`. Fastly only enforces timeouts per backend, so
+    /// clients register one for every combination of backend and timeouts they use. By default it's registered with
+    /// the Fastly host, and one registered earlier in the instance under the same name is reused.
+    fn register_backend(
+        &self,
+        name: &str,
+        builder: impl FnOnce(&str) -> BackendBuilder,
+    ) -> Result<Backend, BoxError> {
+        match builder(name).finish() {
+            Ok(backend) => Ok(backend),
+            Err(BackendCreationError::NameInUse) => Ok(Backend::from_name(name)?),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// The result of [Transport::poll].
+#[derive(Debug)]
+pub enum TransportPoll<P> {
+    /// The request completed.
+    Done(Result<Response, TransportError>),
+    /// The request is still pending.
+    Pending(P),
+}
+
+/// A request that couldn't be sent, or didn't get a response.
+#[derive(Debug)]
+pub struct TransportError {
+    backend: String,
+    cause: SendErrorCause,
+}
+
+impl TransportError {
+    /// Creates an error for a request to `backend` that failed because of `cause`.
+    pub fn new(backend: impl Into<String>, cause: SendErrorCause) -> Self {
+        Self {
+            backend: backend.into(),
+            cause,
+        }
+    }
+
+    /// The name of the backend the request was sent to.
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    /// Why the request failed.
+    pub fn cause(&self) -> &SendErrorCause {
+        &self.cause
+    }
+}
+
+impl From<SendError> for TransportError {
+    fn from(error: SendError) -> Self {
+        Self::new(error.backend_name(), error.root_cause().clone())
+    }
+}
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "request to backend {} failed: {}",
+            self.backend, self.cause
+        )
+    }
+}
+
+impl Error for TransportError {}
+
+/// Sends requests through the Fastly host.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FastlyTransport;
+
+impl Transport for FastlyTransport {
+    type Pending = PendingRequest;
+    type StreamingBody = StreamingBody;
+
+    fn send(&self, request: Request, backend: &Backend) -> Result<PendingRequest, TransportError> {
+        Ok(request.send_async(backend)?)
+    }
+
+    fn send_streaming(
+        &self,
+        request: Request,
+        backend: &Backend,
+    ) -> Result<(StreamingBody, PendingRequest), TransportError> {
+        Ok(request.send_async_streaming(backend)?)
+    }
+
+    fn finish(&self, body: StreamingBody) -> Result<(), BoxError> {
+        Ok(body.finish()?)
+    }
+
+    fn poll(&self, pending: PendingRequest) -> TransportPoll<PendingRequest> {
+        match pending.poll() {
+            PollResult::Done(result) => TransportPoll::Done(result.map_err(TransportError::from)),
+            PollResult::Pending(pending) => TransportPoll::Pending(pending),
+        }
+    }
+
+    fn wait(&self, pending: PendingRequest) -> Result<Response, TransportError> {
+        Ok(pending.wait()?)
+    }
+
+    /// [request::select] doesn't say which request completed, so it's matched up by fingerprinting the sent requests.
+    /// Identical requests are interchangeable, so it doesn't matter which of them is reported when fingerprints
+    /// collide.
+    fn select(
+        &self,
+        pending: Vec<PendingRequest>,
+    ) -> (usize, Result<Response, TransportError>, Vec<PendingRequest>) {
+        let mut fingerprints: Vec<_> = pending
+            .iter()
+            .map(|pending_request| Some(fingerprint(pending_request.sent_req())))
+            .collect();
+
+        let (result, remaining) = request::select(pending);
+
+        let mut remaining: Vec<_> = remaining
+            .into_iter()
+            .map(|pending_request| {
+                let sent = Some(fingerprint(pending_request.sent_req()));
+                let index = fingerprints
+                    .iter()
+                    .position(|other| *other == sent)
+                    .unwrap();
+                fingerprints[index] = None;
+                (index, pending_request)
+            })
+            .collect();
+
+        remaining.sort_by_key(|(index, _)| *index);
+
+        let index = fingerprints.iter().position(Option::is_some).unwrap();
+        let remaining = remaining
+            .into_iter()
+            .map(|(_, pending_request)| pending_request)
+            .collect();
+
+        (index, result.map_err(TransportError::from), remaining)
+    }
+}
+
+fn fingerprint(request: &Request) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    request.get_method().hash(&mut hasher);
+    request.get_url_str().hash(&mut hasher);
+
+    for (name, value) in request.get_headers() {
+        name.hash(&mut hasher);
+        value.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
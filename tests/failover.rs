@@ -0,0 +1,137 @@
+//! Checks how a [FastlyHttpClient] with a [Failover] picks backends, by sending requests straight through its connector
+//! to an [InMemoryTransport]. They call into the Fastly host, so run them in Viceroy with
+//! `cargo test --target wasm32-wasi`.
+
+use aws_fastly_http_client::{block_on, Failover, FastlyHttpClient, InMemoryTransport};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use fastly::http::request::SendErrorCause;
+use fastly::http::StatusCode;
+use fastly::Response;
+
+const URL: &str = "https://dynamodb.us-east-1.amazonaws.com/";
+
+fn failover() -> Failover {
+    Failover::new()
+        .backend("us_east_1")
+        .backend("us_west_2")
+        .backend("eu_west_1")
+        .resign(|request, backend| {
+            request.set_header("x-resigned-for", backend.name());
+            Ok(())
+        })
+}
+
+fn connector(transport: &InMemoryTransport) -> SharedHttpConnector {
+    let http_client = FastlyHttpClient::failover(failover()).with_transport(transport.clone());
+    let components = RuntimeComponentsBuilder::for_tests().build().unwrap();
+
+    http_client.http_connector(&HttpConnectorSettings::builder().build(), &components)
+}
+
+fn send(transport: &InMemoryTransport, body: SdkBody) -> Result<u16, ConnectorError> {
+    let request = http::Request::builder()
+        .method("PUT")
+        .uri(URL)
+        .body(body)
+        .unwrap();
+    let request = HttpRequest::try_from(request).unwrap();
+
+    let response = block_on(connector(transport).call(request))?;
+    Ok(response.status().as_u16())
+}
+
+fn status(status: StatusCode) -> Response {
+    Response::from_status(status)
+}
+
+/// The backends the requests were sent to, and the backend each was signed again for, if any.
+fn sent(transport: &InMemoryTransport) -> Vec<(String, Option<String>)> {
+    transport
+        .requests()
+        .iter()
+        .map(|request| {
+            let resigned = request.header("x-resigned-for").map(str::to_string);
+            (request.backend().to_string(), resigned)
+        })
+        .collect()
+}
+
+#[test]
+fn fails_over_when_a_backend_cant_be_reached() {
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::ConnectionRefused)
+        .fail(SendErrorCause::DnsTimeout)
+        .respond(status(StatusCode::OK));
+
+    assert_eq!(send(&transport, SdkBody::from("item")).unwrap(), 200);
+    assert_eq!(
+        sent(&transport),
+        [
+            ("us_east_1".to_string(), None),
+            ("us_west_2".to_string(), Some("us_west_2".to_string())),
+            ("eu_west_1".to_string(), Some("eu_west_1".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn fails_over_on_failover_statuses() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond(status(StatusCode::SERVICE_UNAVAILABLE))
+        .respond(status(StatusCode::OK));
+
+    assert_eq!(send(&transport, SdkBody::from("item")).unwrap(), 200);
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn returns_other_statuses_as_is() {
+    let transport = InMemoryTransport::new();
+    transport.respond(status(StatusCode::BAD_REQUEST));
+
+    assert_eq!(send(&transport, SdkBody::from("item")).unwrap(), 400);
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn returns_the_last_backends_outcome() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond(status(StatusCode::BAD_GATEWAY))
+        .respond(status(StatusCode::GATEWAY_TIMEOUT))
+        .respond(status(StatusCode::SERVICE_UNAVAILABLE));
+
+    assert_eq!(send(&transport, SdkBody::from("item")).unwrap(), 503);
+
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::ConnectionRefused)
+        .fail(SendErrorCause::ConnectionRefused)
+        .fail(SendErrorCause::ConnectionTimeout);
+
+    assert!(send(&transport, SdkBody::from("item"))
+        .unwrap_err()
+        .is_timeout());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn sends_streaming_bodies_to_the_first_backend_only() {
+    let transport = InMemoryTransport::new();
+    transport.respond(status(StatusCode::SERVICE_UNAVAILABLE));
+
+    let body = SdkBody::from_body_0_4(http_body::Full::new(Bytes::from("upload")));
+
+    assert_eq!(send(&transport, body).unwrap(), 503);
+    assert_eq!(sent(&transport), [("us_east_1".to_string(), None)]);
+    assert_eq!(transport.requests()[0].body(), b"upload");
+}
@@ -0,0 +1,77 @@
+//! Checks that [InMemoryTransport] behaves like a backend would: it fails requests with the scripted causes and
+//! records streamed request bodies. Requests and responses live in the Fastly host, so run them in Viceroy with
+//! `cargo test --target wasm32-wasi`.
+
+use std::io::Write;
+
+use aws_fastly_http_client::{InMemoryTransport, Transport, TransportPoll};
+use fastly::http::request::SendErrorCause;
+use fastly::{Backend, Request, Response};
+
+const URL: &str = "https://dynamodb.us-east-1.amazonaws.com/";
+
+fn backend() -> Backend {
+    Backend::from_name("dynamodb").unwrap()
+}
+
+#[test]
+fn fails_with_the_scripted_cause() {
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::ConnectionRefused)
+        .fail_after(2, SendErrorCause::DnsTimeout);
+
+    let pending = transport.send(Request::get(URL), &backend()).unwrap();
+    let error = transport.wait(pending).unwrap_err();
+
+    assert!(matches!(error.cause(), SendErrorCause::ConnectionRefused));
+    assert_eq!(error.backend(), "dynamodb");
+
+    let pending = transport.send(Request::get(URL), &backend()).unwrap();
+    let TransportPoll::Pending(pending) = transport.poll(pending) else {
+        panic!("completed before its delay");
+    };
+    let error = transport.wait(pending).unwrap_err();
+
+    assert!(matches!(error.cause(), SendErrorCause::DnsTimeout));
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn records_streamed_bodies() {
+    let transport = InMemoryTransport::new();
+    transport.respond(Response::from_body("done"));
+
+    let request = Request::put(URL).with_body("first ");
+    let (mut body, pending) = transport.send_streaming(request, &backend()).unwrap();
+
+    body.write_all(b"second ").unwrap();
+    body.write_all(b"third").unwrap();
+    transport.finish(body).unwrap();
+
+    let mut response = transport.wait(pending).unwrap();
+    assert_eq!(response.take_body_str(), "done");
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].method(), "PUT");
+    assert_eq!(requests[0].url(), URL);
+    assert_eq!(requests[0].backend(), "dynamodb");
+    assert_eq!(requests[0].body(), b"first second third");
+}
+
+#[test]
+fn selects_the_first_request_to_complete() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond_after(5, Response::from_body("slow"))
+        .respond_after(1, Response::from_body("fast"));
+
+    let slow = transport.send(Request::get(URL), &backend()).unwrap();
+    let fast = transport.send(Request::get(URL), &backend()).unwrap();
+
+    let (index, result, remaining) = transport.select(vec![slow, fast]);
+
+    assert_eq!(index, 1);
+    assert_eq!(result.unwrap().take_body_str(), "fast");
+    assert_eq!(remaining.len(), 1);
+}
@@ -0,0 +1,88 @@
+//! Records DynamoDB calls made through an [InMemoryTransport] with a [RecordingFastlyHttpClient] and replays them with a
+//! [ReplayHttpClient]. Recording calls into the Fastly host, so run them in Viceroy with
+//! `cargo test --target wasm32-wasi`.
+
+use aws_fastly_http_client::{
+    block_on, FastlyHttpClient, FastlySleep, FastlyTimeSource, Fixture, InMemoryTransport,
+    RecordingFastlyHttpClient, ReplayHttpClient,
+};
+use aws_sdk_dynamodb::config::retry::RetryConfig;
+use aws_sdk_dynamodb::config::timeout::TimeoutConfig;
+use aws_sdk_dynamodb::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::get_item::{GetItemError, GetItemOutput};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_smithy_runtime_api::client::http::HttpClient;
+use fastly::Response;
+
+const ITEM: &str = r#"{"Item":{"path":{"S":"/"}}}"#;
+
+fn client(http_client: impl HttpClient + 'static) -> aws_sdk_dynamodb::Client {
+    let config = aws_sdk_dynamodb::Config::builder()
+        .region(Region::from_static("us-east-1"))
+        .credentials_provider(Credentials::new("AKID", "SECRET", None, None, "test"))
+        .http_client(http_client)
+        .sleep_impl(FastlySleep)
+        .time_source(FastlyTimeSource)
+        .retry_config(RetryConfig::disabled())
+        .timeout_config(TimeoutConfig::disabled())
+        .behavior_version(BehaviorVersion::v2023_11_09())
+        .build();
+
+    aws_sdk_dynamodb::Client::from_conf(config)
+}
+
+async fn get_item(
+    client: &aws_sdk_dynamodb::Client,
+    path: &str,
+) -> Result<GetItemOutput, SdkError<GetItemError>> {
+    client
+        .get_item()
+        .table_name("paths")
+        .key("path", AttributeValue::S(path.to_string()))
+        .send()
+        .await
+}
+
+/// Records a `GetItem` call for the item with path `/`.
+fn record() -> Fixture {
+    let transport = InMemoryTransport::new();
+    transport.respond(Response::from_body(ITEM));
+
+    let http_client =
+        RecordingFastlyHttpClient::new(FastlyHttpClient::dynamic().with_transport(transport));
+
+    block_on(get_item(&client(http_client.clone()), "/")).unwrap();
+
+    http_client.fixture()
+}
+
+#[test]
+fn replays_recorded_calls() {
+    let fixture = record();
+    assert_eq!(fixture.len(), 1);
+
+    // Signed again, at another time and with another invocation id.
+    let fixture = Fixture::from_json(&fixture.to_json()).unwrap();
+    let http_client = ReplayHttpClient::new(fixture);
+
+    let output = block_on(get_item(&client(http_client.clone()), "/")).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(http_client.remaining(), 0);
+}
+
+#[test]
+fn round_trips_fixtures_through_json() {
+    let fixture = record();
+
+    assert_eq!(Fixture::from_json(&fixture.to_json()).unwrap(), fixture);
+}
+
+#[test]
+#[should_panic(expected = "request doesn't match the recorded one")]
+fn panics_on_requests_that_dont_match() {
+    let http_client = ReplayHttpClient::new(record());
+
+    let _ = block_on(get_item(&client(http_client), "/other"));
+}
@@ -0,0 +1,380 @@
+//! End to end examples of the SDK's retries and timeouts running on [FastlyHttpClient], with responses scripted by an
+//! [InMemoryTransport]. They call into the Fastly host, so run them in Viceroy with `cargo test --target wasm32-wasi`.
+
+use std::thread;
+use std::time::Duration;
+
+use aws_fastly_http_client::{
+    block_on, CircuitBreaker, Collapsing, FastlyHttpClient, FastlyRetryClassifier, FastlySleep,
+    FastlyTimeSource, Hedging, InMemoryTransport,
+};
+use aws_sdk_dynamodb::config::retry::RetryConfig;
+use aws_sdk_dynamodb::config::timeout::TimeoutConfig;
+use aws_sdk_dynamodb::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::get_item::{GetItemError, GetItemOutput};
+use aws_sdk_dynamodb::types::AttributeValue;
+use fastly::http::request::SendErrorCause;
+use fastly::http::StatusCode;
+use fastly::Response;
+
+const ITEM: &str = r#"{"Item":{"path":{"S":"/"}}}"#;
+const INTERNAL_SERVER_ERROR: &str = r#"{"__type":"com.amazonaws.dynamodb.v20120810#InternalServerError","message":"Internal server error"}"#;
+
+fn client(
+    http_client: FastlyHttpClient<InMemoryTransport>,
+    retry_config: RetryConfig,
+    timeout_config: TimeoutConfig,
+) -> aws_sdk_dynamodb::Client {
+    let config = aws_sdk_dynamodb::Config::builder()
+        .region(Region::from_static("us-east-1"))
+        .credentials_provider(Credentials::new("AKID", "SECRET", None, None, "test"))
+        .http_client(http_client)
+        .sleep_impl(FastlySleep)
+        .time_source(FastlyTimeSource)
+        .retry_config(retry_config.with_initial_backoff(Duration::from_millis(10)))
+        .retry_classifier(FastlyRetryClassifier)
+        .timeout_config(timeout_config)
+        .behavior_version(BehaviorVersion::v2023_11_09())
+        .build();
+
+    aws_sdk_dynamodb::Client::from_conf(config)
+}
+
+async fn get_item(
+    client: &aws_sdk_dynamodb::Client,
+) -> Result<GetItemOutput, SdkError<GetItemError>> {
+    client
+        .get_item()
+        .table_name("paths")
+        .key("path", AttributeValue::S("/".to_string()))
+        .send()
+        .await
+}
+
+#[test]
+fn retries_with_backoff() {
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::ConnectionRefused)
+        .respond(
+            Response::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+                .with_body(INTERNAL_SERVER_ERROR),
+        )
+        .respond(Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::standard().with_max_attempts(3),
+        TimeoutConfig::disabled(),
+    );
+
+    let output = block_on(get_item(&client)).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn retries_dns_timeouts_and_connection_limits() {
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::DnsTimeout)
+        .fail(SendErrorCause::ConnectionLimitReached)
+        .respond(Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::standard().with_max_attempts(3),
+        TimeoutConfig::disabled(),
+    );
+
+    let output = block_on(get_item(&client)).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn doesnt_retry_incomplete_responses_to_posts() {
+    // DynamoDB calls are all POSTs, so they might have been carried out already.
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::HttpIncompleteResponse)
+        .respond(Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::standard().with_max_attempts(3),
+        TimeoutConfig::disabled(),
+    );
+
+    let error = block_on(get_item(&client)).unwrap_err();
+
+    assert!(matches!(error, SdkError::DispatchFailure(_)), "{error:?}");
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn doesnt_retry_tls_configuration_errors() {
+    let transport = InMemoryTransport::new();
+    transport
+        .fail(SendErrorCause::TlsConfigurationError)
+        .respond(Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::standard().with_max_attempts(3),
+        TimeoutConfig::disabled(),
+    );
+
+    assert!(block_on(get_item(&client)).is_err());
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn times_out_slow_attempts() {
+    let transport = InMemoryTransport::new();
+    transport.respond_after(u32::MAX, Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::disabled(),
+        TimeoutConfig::builder()
+            .operation_attempt_timeout(Duration::from_millis(50))
+            .build(),
+    );
+
+    let error = block_on(get_item(&client)).unwrap_err();
+
+    assert!(matches!(error, SdkError::TimeoutError(_)), "{error:?}");
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn retries_timed_out_attempts() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond_after(u32::MAX, Response::from_body(ITEM))
+        .respond(Response::from_body(ITEM));
+
+    let client = client(
+        FastlyHttpClient::dynamic().with_transport(transport.clone()),
+        RetryConfig::standard(),
+        TimeoutConfig::builder()
+            .operation_attempt_timeout(Duration::from_millis(50))
+            .build(),
+    );
+
+    let output = block_on(get_item(&client)).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn queues_requests_over_the_limit() {
+    let transport = InMemoryTransport::new();
+    for polls in 0..5 {
+        transport.respond_after(polls, Response::from_body(ITEM));
+    }
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_max_in_flight(2);
+
+    let client = client(
+        http_client.clone(),
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    );
+
+    let outputs = block_on(futures::future::join_all((0..5).map(|_| get_item(&client))));
+
+    assert!(outputs
+        .into_iter()
+        .all(|output| output.unwrap().item.is_some()));
+    assert_eq!(transport.requests().len(), 5);
+    assert!(transport.peak_pending() <= 2);
+
+    let metrics = http_client.concurrency_metrics();
+    assert_eq!(metrics.in_flight(), 0);
+    assert_eq!(metrics.queued(), 0);
+    assert!(metrics.peak_queued() > 0);
+}
+
+#[test]
+#[should_panic(expected = "max_in_flight must be at least 1")]
+fn rejects_a_limit_of_zero() {
+    let _ = FastlyHttpClient::dynamic().with_max_in_flight(0);
+}
+
+#[test]
+fn hedges_slow_reads() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond_after(u32::MAX, Response::from_body(ITEM))
+        .respond(Response::from_body(ITEM));
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_hedging(Hedging::new(Duration::from_millis(10)));
+
+    let client = client(
+        http_client,
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    );
+
+    let output = block_on(get_item(&client)).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn only_hedges_within_the_limit() {
+    let transport = InMemoryTransport::new();
+    transport.respond_after(50, Response::from_body(ITEM));
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_max_in_flight(1)
+        .with_hedging(Hedging::new(Duration::from_millis(10)));
+
+    let client = client(
+        http_client,
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    );
+
+    let output = block_on(get_item(&client)).unwrap();
+
+    assert!(output.item.is_some());
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[test]
+fn collapses_identical_reads() {
+    let transport = InMemoryTransport::new();
+    transport.respond_after(3, item_with_length());
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_collapsing(Collapsing::new());
+
+    let client = client(
+        http_client,
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    );
+
+    let outputs = block_on(futures::future::join_all((0..5).map(|_| get_item(&client))));
+
+    assert!(outputs
+        .into_iter()
+        .all(|output| output.unwrap().item.is_some()));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+fn item_with_length() -> Response {
+    Response::from_body(ITEM).with_header("content-length", ITEM.len().to_string())
+}
+
+#[test]
+fn sends_reads_with_large_responses_on_their_own() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond_after(3, item_with_length())
+        .respond_after(3, item_with_length());
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_collapsing(Collapsing::new().max_body_size(8));
+
+    let client = client(
+        http_client,
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    );
+
+    let outputs = block_on(futures::future::join_all((0..2).map(|_| get_item(&client))));
+
+    assert!(outputs
+        .into_iter()
+        .all(|output| output.unwrap().item.is_some()));
+    assert_eq!(transport.requests().len(), 2);
+}
+
+fn circuit_client(transport: &InMemoryTransport) -> aws_sdk_dynamodb::Client {
+    let breaker = CircuitBreaker::new()
+        .failure_rate(0.5)
+        .minimum_requests(2)
+        .window(Duration::from_millis(50))
+        .open_for(Duration::from_millis(50));
+
+    let http_client = FastlyHttpClient::dynamic()
+        .with_transport(transport.clone())
+        .with_circuit_breaker(breaker);
+
+    client(
+        http_client,
+        RetryConfig::disabled(),
+        TimeoutConfig::disabled(),
+    )
+}
+
+fn server_error() -> Response {
+    Response::from_status(StatusCode::INTERNAL_SERVER_ERROR).with_body(INTERNAL_SERVER_ERROR)
+}
+
+#[test]
+fn starts_counting_anew_every_window() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond(server_error())
+        .respond(Response::from_body(ITEM))
+        .respond(Response::from_body(ITEM));
+
+    let client = circuit_client(&transport);
+
+    assert!(block_on(get_item(&client)).is_err());
+    thread::sleep(Duration::from_millis(60));
+
+    // Counted along with the failure, these would have opened the circuit.
+    assert!(block_on(get_item(&client)).is_ok());
+    assert!(block_on(get_item(&client)).is_ok());
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[test]
+fn probes_an_open_circuit_until_the_backend_recovers() {
+    let transport = InMemoryTransport::new();
+    transport
+        .respond(server_error())
+        .respond(server_error())
+        .respond(server_error())
+        .respond(Response::from_body(ITEM))
+        .respond(Response::from_body(ITEM));
+
+    let client = circuit_client(&transport);
+
+    assert!(block_on(get_item(&client)).is_err());
+    assert!(block_on(get_item(&client)).is_err());
+
+    // Open: fails without sending anything.
+    assert!(block_on(get_item(&client)).is_err());
+    assert_eq!(transport.requests().len(), 2);
+
+    // A failed probe opens the circuit again.
+    thread::sleep(Duration::from_millis(60));
+    assert!(block_on(get_item(&client)).is_err());
+    assert!(block_on(get_item(&client)).is_err());
+    assert_eq!(transport.requests().len(), 3);
+
+    // A successful probe closes it.
+    thread::sleep(Duration::from_millis(60));
+    assert!(block_on(get_item(&client)).is_ok());
+    assert!(block_on(get_item(&client)).is_ok());
+    assert_eq!(transport.requests().len(), 5);
+}